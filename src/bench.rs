@@ -0,0 +1,117 @@
+use crate::{
+    err_new, err_new_image, err_new_io,
+    error::{Kind, Result},
+    swiping_img::{BigImg, Chunk},
+};
+use image::{DynamicImage, Rgb, RgbImage};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// 生成图像块的源图片尺寸，与常见的手机截图接近
+const SOURCE_SIZE: (u32, u32) = (1080, 1440);
+
+/// 在生成的数据集上比较单线程与多线程绘制图像块的耗时。
+///
+/// 数据集保存在 `work_dir/bench` 中，已存在的图片直接复用。
+///
+/// # Parameters
+/// - `work_dir`: 工作路径。
+/// - `count`: 生成的图像块数量。
+/// - `workers`: 多线程绘制使用的线程数，为 `None` 时使用 CPU 核心数。
+///
+/// # Errors
+/// - 如果生成数据集、构建 `BigImg` 或绘制图像块时发生错误，则返回 `Err`。
+/// - 如果多线程绘制的结果与单线程不一致，则返回 `Err`。
+///
+pub fn run(work_dir: &Path, count: usize, workers: Option<usize>) -> Result<()> {
+    let chunks = dataset(&work_dir.join("bench"), count)?;
+    let workers =
+        workers.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, usize::from));
+
+    let (serial, expected) = measure(work_dir, &chunks, 1)?;
+    let (parallel, images) = measure(work_dir, &chunks, workers)?;
+    // 多线程绘制的结果应与单线程完全一致
+    if images != expected {
+        return Err(err_new!(
+            Kind::Other,
+            "parallel rendering changed the output"
+        ));
+    }
+    print!("{}", summary(serial, parallel, workers));
+    Ok(())
+}
+
+/// 单线程与多线程绘制耗时的对比结果，每项一行
+fn summary(serial: Duration, parallel: Duration, workers: usize) -> String {
+    format!(
+        "1 worker: {} ms\n{workers} workers: {} ms\nspeedup: {:.2}x\n",
+        serial.as_millis(),
+        parallel.as_millis(),
+        serial.as_secs_f64() / parallel.as_secs_f64().max(f64::EPSILON)
+    )
+}
+
+/// 使用 `workers` 个线程绘制所有图像块，返回耗时和绘制结果
+fn measure(
+    work_dir: &Path,
+    chunks: &[Chunk],
+    workers: usize,
+) -> Result<(Duration, Vec<DynamicImage>)> {
+    let si = BigImg::builder(work_dir, chunks)
+        .workers(workers)
+        .cache(false)
+        .build()?;
+    let t = Instant::now();
+    let images = si.render_chunks(0..chunks.len())?;
+    Ok((t.elapsed(), images))
+}
+
+/// 在 `dir` 中生成 `count` 张渐变图片及对应的图像块
+fn dataset(dir: &Path, count: usize) -> Result<Vec<Chunk>> {
+    fs::create_dir_all(dir).map_err(|e| err_new_io!(e))?;
+    (0..count)
+        .map(|i| {
+            let path = dir.join(format!("{i:0>4}.png"));
+            if !path.exists() {
+                let shift = (i * 37 % 256) as u8;
+                let (w, h) = SOURCE_SIZE;
+                RgbImage::from_fn(w, h, |x, y| {
+                    Rgb([
+                        (x * 255 / w) as u8 ^ shift,
+                        (y * 255 / h) as u8,
+                        ((x + y) % 256) as u8,
+                    ])
+                })
+                .save(&path)
+                .map_err(|e| err_new_image!(e))?;
+            }
+            Chunk::new(
+                path,
+                vec![format!("名字{i}"), "生日".to_string()],
+                vec![format!("{:0>2}月{:0>2}日", i % 12 + 1, i % 28 + 1)],
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            summary(Duration::from_millis(900), Duration::from_millis(300), 4),
+            "1 worker: 900 ms\n4 workers: 300 ms\nspeedup: 3.00x\n"
+        );
+        // 多线程耗时为 0 时不输出 `inf` 或 `NaN`
+        let summary = summary(Duration::ZERO, Duration::ZERO, 2);
+        assert!(
+            !summary.contains("inf") && !summary.contains("NaN"),
+            "{summary}"
+        );
+    }
+}
@@ -0,0 +1,633 @@
+use crate::{
+    bench, err_new, err_new_io,
+    error::{Kind, Result},
+    prelude::debug_print,
+    swiping_img::{
+        config::parse_color, load_font, AudioTrack, Axis, BigImg, BigImgBuilder, BigImgConfig,
+        Chunk, Direction, EncoderSettings, MotionProfile, OutputFormat, Pipeline, Preview,
+        Progress, SwipSpeed, VideoCodec,
+    },
+};
+use clap::{Args, Parser, Subcommand};
+use image::Rgba;
+use std::{
+    fs::{self, File},
+    io::IsTerminal,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// 将图片数据集渲染为滑动视频
+#[derive(Parser)]
+#[command(name = "to_video", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 渲染完整视频
+    Render {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        builder: BuilderArgs,
+        /// 输出视频文件名，相对于工作路径
+        #[arg(short, long, default_value = "result.mp4")]
+        output: PathBuf,
+        /// 渲染前删除当前数据集和参数不再使用的图像块缓存
+        #[arg(long)]
+        prune_cache: bool,
+        /// 在 stderr 中输出渲染进度
+        #[arg(long)]
+        progress: bool,
+        /// 只输出分段、时长和 ffmpeg 命令，不读取图片也不调用 ffmpeg
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 只生成封面预览图像，不调用 ffmpeg
+    Preview {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        builder: BuilderArgs,
+        /// 输出图像文件名，相对于工作路径
+        #[arg(short, long, default_value = "preview.png")]
+        output: PathBuf,
+    },
+    /// 只生成视频在指定时间显示的画面，不调用 ffmpeg
+    Frame {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        builder: BuilderArgs,
+        /// 视频中的时间，如 `12.5s`、`12.5` 或 `1:02.5`
+        #[arg(long, value_parser = parse_time)]
+        at: f64,
+        /// 输出图像文件名，相对于工作路径
+        #[arg(short, long, default_value = "frame.png")]
+        output: PathBuf,
+    },
+    /// 校验数据集与参数，不生成任何文件
+    Validate {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        builder: BuilderArgs,
+    },
+    /// 在生成的数据集上比较单线程与多线程绘制图像块的耗时
+    Bench {
+        /// 工作路径，用于保存生成的数据集
+        #[arg(short, long)]
+        work_dir: PathBuf,
+        /// 生成的图像块数量
+        #[arg(short, long, default_value_t = 64)]
+        count: usize,
+        /// 多线程绘制使用的线程数，默认为 CPU 核心数
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        workers: Option<u64>,
+    },
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// 数据集 json 文件
+    #[arg(short, long)]
+    data: PathBuf,
+    /// 工作路径，用于保存生成的文件
+    #[arg(short, long)]
+    work_dir: PathBuf,
+    /// 使用的 chunk 范围，例如 `0..60`、`10..`、`..60`
+    #[arg(short, long, value_parser = parse_range)]
+    range: Option<ChunkRange>,
+}
+
+#[derive(Args)]
+struct BuilderArgs {
+    /// 项目文件（TOML 或 JSON），命令行参数会覆盖其中的设置
+    #[arg(short, long)]
+    project: Option<PathBuf>,
+    /// 屏幕分辨率，例如 `1920x1080`
+    #[arg(long, value_parser = parse_screen)]
+    screen: Option<(u32, u32)>,
+    /// 每次处理图像块的列数
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    step: Option<u32>,
+    /// 每个图像块沿滑动方向的长度，横向滑动时为宽度，纵向滑动时为高度
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    width_chunk: Option<u32>,
+    /// 图像块沿滑动方向的长度范围，例如 `240-720`，给出时长度随图片的宽高比变化，不再使用 `width_chunk`
+    #[arg(long, value_parser = parse_fit_width)]
+    fit_width: Option<(u32, u32)>,
+    /// 滑动方向，`horizontal` 或 `vertical`
+    #[arg(long)]
+    axis: Option<Axis>,
+    /// 每列图像块的数量，同一列的图像块平分垂直于滑动方向的屏幕长度
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    rows: Option<u32>,
+    /// 文本背景颜色，上下两种，例如 `#1796eb,#2c5599`
+    #[arg(long, value_parser = parse_color_pair)]
+    text_background_color: Option<(Rgba<u8>, Rgba<u8>)>,
+    /// 文本颜色，例如 `#ffffff` 或 `#ffffffff`
+    #[arg(long, value_parser = parse_color)]
+    text_color: Option<Rgba<u8>>,
+    /// 字体的最大缩放因子
+    #[arg(long)]
+    max_scale: Option<f32>,
+    /// 图像块中的图片区域高度
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pic_h: Option<u32>,
+    /// 图像块中的上方文本高度
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    text_up_h: Option<u32>,
+    /// 字体文件路径
+    #[arg(long)]
+    font: Option<PathBuf>,
+    /// 视频封面的持续时间（秒）
+    #[arg(long)]
+    video_cover_time: Option<u32>,
+    /// 视频结尾的持续时间（秒）
+    #[arg(long)]
+    video_ending_time: Option<u32>,
+    /// 视频背景颜色，使用 ffmpeg 的颜色表示
+    #[arg(long)]
+    video_background_color: Option<String>,
+    /// 滑动速度，滑动一个图像块所需的秒数（例如 `2.5` 或 `2.5s`）或每秒滑动的像素数（例如 `300px/s`）
+    #[arg(long)]
+    video_swip_speed: Option<SwipSpeed>,
+    /// 视频帧率
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    video_fps: Option<u32>,
+    /// 视频生成方式，`segments` 或 `stream`
+    #[arg(long)]
+    pipeline: Option<Pipeline>,
+    /// 运动曲线，`linear`、`ease:IN,OUT`、`ease-in:IN`、`ease-out:OUT` 或 `step-hold:HOLD`（秒）
+    #[arg(long)]
+    motion: Option<MotionProfile>,
+    /// 滑动方向，`forward`、`reverse` 或 `ping-pong`
+    #[arg(long)]
+    direction: Option<Direction>,
+    /// 音频文件，可以多次给出以混合多个音频
+    #[arg(long)]
+    audio: Vec<PathBuf>,
+    /// 命令行给出的音频的音量倍数
+    #[arg(long, default_value_t = 1.0)]
+    audio_volume: f64,
+    /// 命令行给出的音频的淡入时长（秒）
+    #[arg(long, default_value_t = 0.0)]
+    audio_fade_in: f64,
+    /// 命令行给出的音频的淡出时长（秒）
+    #[arg(long, default_value_t = 0.0)]
+    audio_fade_out: f64,
+    /// 命令行给出的音频中节拍标记的时间（秒），与滑动阶段的开始对齐
+    #[arg(long)]
+    audio_beat: Option<f64>,
+    /// 命令行给出的音频比视频短时不循环播放
+    #[arg(long)]
+    no_audio_loop: bool,
+    /// 生成与图像块同步的 SRT 和 WebVTT 字幕文件
+    #[arg(long)]
+    subtitles: bool,
+    /// 在视频中写入与图像块同步的章节
+    #[arg(long)]
+    chapters: bool,
+    /// 输出格式，`mp4`、`webm`、`gif[:COLORS]`、`webp[:QUALITY|lossless]`、`apng` 或 `png-sequence`，
+    /// 未给出时根据输出文件的扩展名推断
+    #[arg(long)]
+    format: Option<OutputFormat>,
+    /// 视频编码器，`libx264`、`libx265` 或 `libvpx-vp9`
+    #[arg(long)]
+    codec: Option<VideoCodec>,
+    /// 恒定质量因子，越小质量越高
+    #[arg(long)]
+    crf: Option<u8>,
+    /// 目标码率，例如 `4M`
+    #[arg(long)]
+    bitrate: Option<String>,
+    /// 像素格式，例如 `yuv420p`
+    #[arg(long)]
+    pix_fmt: Option<String>,
+    /// 编码档次，例如 `high`
+    #[arg(long)]
+    profile: Option<String>,
+    /// 编码速度预设，例如 `fast`、`slow`
+    #[arg(long)]
+    preset: Option<String>,
+    /// 关键帧间隔（帧数）
+    #[arg(long)]
+    gop: Option<u32>,
+    /// 并行绘制图像块的线程数，默认为 CPU 核心数
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    workers: Option<u64>,
+    /// 不读取也不写入图像块缓存，也不保留和复用分段视频
+    #[arg(long)]
+    no_cache: bool,
+    /// FFmpeg 可执行文件的路径，默认依次使用环境变量 `FFMPEG_PATH` 和 `PATH` 中的 `ffmpeg`
+    #[arg(long)]
+    ffmpeg: Option<PathBuf>,
+    /// 生成低分辨率的草稿视频：按比例缩小尺寸和字体，降低帧率并使用更快的编码预设。
+    /// 与只生成封面图像的 `preview` 子命令不同
+    #[arg(long)]
+    draft: bool,
+    /// 草稿的尺寸缩放比例，范围为 `(0, 1]`
+    #[arg(long, requires = "draft", default_value_t = Preview::default().scale)]
+    draft_scale: f32,
+    /// 草稿视频的帧率
+    #[arg(long, requires = "draft", default_value_t = Preview::default().fps)]
+    draft_fps: u32,
+    /// 草稿只生成前几个分段
+    #[arg(long, requires = "draft", value_parser = clap::value_parser!(u64).range(1..))]
+    draft_segments: Option<u64>,
+}
+
+#[derive(Clone, Copy)]
+struct ChunkRange {
+    start: Option<usize>,
+    end: Option<usize>,
+}
+
+impl Cli {
+    /// 执行解析得到的子命令。
+    ///
+    /// # Errors
+    /// - 如果读取数据集、构建 `BigImg` 或渲染过程中发生错误，则返回 `Err`。
+    ///
+    pub fn run(&self) -> Result<()> {
+        match &self.command {
+            Command::Render {
+                input,
+                builder,
+                output,
+                prune_cache,
+                progress,
+                dry_run,
+            } => {
+                let chunks = input.load()?;
+                let mut builder = builder.apply(input.builder(&chunks)?)?;
+                if *progress {
+                    builder.progress(print_progress);
+                }
+                let si = builder.build()?;
+                if *dry_run {
+                    print!("{}", si.plan(output)?);
+                    return Ok(());
+                }
+                debug_print(&si);
+                if *prune_cache {
+                    println!("{} cache entries pruned", si.prune_cache()?);
+                }
+                si.run(output)
+            }
+            Command::Preview {
+                input,
+                builder,
+                output,
+            } => {
+                let chunks = input.load()?;
+                let si = builder.apply(input.builder(&chunks)?)?.build()?;
+                si.save_cover(output)?;
+                println!("{} successed", output.display());
+                Ok(())
+            }
+            Command::Frame {
+                input,
+                builder,
+                at,
+                output,
+            } => {
+                let chunks = input.load()?;
+                let si = builder.apply(input.builder(&chunks)?)?.build()?;
+                si.save_frame_at(*at, output)?;
+                println!("{} successed", output.display());
+                Ok(())
+            }
+            Command::Validate { input, builder } => {
+                let chunks = input.load()?;
+                builder.apply(input.builder(&chunks)?)?.build()?;
+                let invalid = chunks
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, chunk)| chunk.check().err().map(|e| (i, e)))
+                    .inspect(|(i, e)| println!("chunk {i}: {e}"))
+                    .count();
+                if invalid > 0 {
+                    return Err(err_new!(
+                        Kind::Other,
+                        &format!("{invalid} of {} chunks are invalid", chunks.len())
+                    ));
+                }
+                println!("{} chunks are valid", chunks.len());
+                Ok(())
+            }
+            Command::Bench {
+                work_dir,
+                count,
+                workers,
+            } => bench::run(work_dir, *count, workers.map(usize::try_from).transpose()?),
+        }
+    }
+}
+
+impl InputArgs {
+    /// 读取数据集并截取 `range` 范围内的 chunk
+    fn load(&self) -> Result<Vec<Chunk>> {
+        let mut chunks: Vec<Chunk> = read_json(&self.data)?;
+        if let Some(range) = self.range {
+            let range = range.resolve(chunks.len())?;
+            chunks.truncate(range.end);
+            chunks.drain(..range.start);
+        }
+        Ok(chunks)
+    }
+
+    /// 创建工作路径并返回对应的 `BigImgBuilder`
+    fn builder<'a>(&self, chunks: &'a [Chunk]) -> Result<BigImgBuilder<'a>> {
+        fs::create_dir_all(&self.work_dir).map_err(|e| err_new_io!(e))?;
+        Ok(BigImg::builder(&self.work_dir, chunks))
+    }
+}
+
+impl BuilderArgs {
+    /// 依次将项目文件和命令行中给出的参数写入 `BigImgBuilder`，未给出的参数保持默认值
+    fn apply<'a>(&self, mut builder: BigImgBuilder<'a>) -> Result<BigImgBuilder<'a>> {
+        let config = match &self.project {
+            Some(project) => BigImgConfig::load(project)?,
+            None => BigImgConfig::default(),
+        };
+        config.apply(&mut builder)?;
+        if let Some(screen) = self.screen {
+            builder.screen(screen);
+        }
+        if let Some(step) = self.step {
+            builder.step(step);
+        }
+        if let Some(width_chunk) = self.width_chunk {
+            builder.width_chunk(width_chunk);
+        }
+        if let Some((min, max)) = self.fit_width {
+            builder.fit_width(min, max);
+        }
+        if let Some(axis) = self.axis {
+            builder.axis(axis);
+        }
+        if let Some(rows) = self.rows {
+            builder.rows(rows);
+        }
+        if let Some(color) = self.text_background_color {
+            builder.text_background_color(color);
+        }
+        if let Some(color) = self.text_color {
+            builder.text_color(color);
+        }
+        if let Some(max_scale) = self.max_scale {
+            builder.max_scale(max_scale);
+        }
+        if let Some(pic_h) = self.pic_h {
+            builder.pic_h(pic_h);
+        }
+        if let Some(text_up_h) = self.text_up_h {
+            builder.text_up_h(text_up_h);
+        }
+        if let Some(font) = &self.font {
+            builder.font(load_font(font)?);
+        }
+        if let Some(time) = self.video_cover_time {
+            builder.video_cover_time(time);
+        }
+        if let Some(time) = self.video_ending_time {
+            builder.video_ending_time(time);
+        }
+        if let Some(color) = &self.video_background_color {
+            builder.video_background_color(color.clone());
+        }
+        if let Some(speed) = self.video_swip_speed {
+            builder.video_swip_speed(speed);
+        }
+        if let Some(fps) = self.video_fps {
+            builder.video_fps(fps);
+        }
+        if let Some(pipeline) = self.pipeline {
+            builder.pipeline(pipeline);
+        }
+        if let Some(motion) = self.motion {
+            builder.motion(motion);
+        }
+        if let Some(direction) = self.direction {
+            builder.direction(direction);
+        }
+        for path in &self.audio {
+            builder.audio(AudioTrack {
+                volume: self.audio_volume,
+                fade_in: self.audio_fade_in,
+                fade_out: self.audio_fade_out,
+                looped: !self.no_audio_loop,
+                beat: self.audio_beat,
+                ..AudioTrack::new(path)
+            });
+        }
+        if self.subtitles {
+            builder.subtitles(true);
+        }
+        if self.chapters {
+            builder.chapters(true);
+        }
+        if let Some(format) = self.format {
+            builder.output_format(format);
+        }
+        builder.encoder(self.encoder(config.encoder.unwrap_or_default()));
+        if let Some(workers) = self.workers {
+            builder.workers(usize::try_from(workers)?);
+        }
+        if self.no_cache {
+            builder.cache(false);
+        }
+        if let Some(ffmpeg) = &self.ffmpeg {
+            builder.ffmpeg(ffmpeg.clone());
+        }
+        if self.draft {
+            builder.preview(Preview {
+                scale: self.draft_scale,
+                fps: self.draft_fps,
+                segments: self.draft_segments.map(usize::try_from).transpose()?,
+                ..Preview::default()
+            });
+        }
+        Ok(builder)
+    }
+
+    /// 用命令行给出的编码参数覆盖项目文件中的编码参数
+    fn encoder(&self, mut encoder: EncoderSettings) -> EncoderSettings {
+        if let Some(codec) = self.codec {
+            encoder.codec = codec;
+        }
+        if let Some(crf) = self.crf {
+            encoder.crf = Some(crf);
+        }
+        if let Some(bitrate) = &self.bitrate {
+            encoder.bitrate = Some(bitrate.clone());
+        }
+        if let Some(pix_fmt) = &self.pix_fmt {
+            encoder.pix_fmt.clone_from(pix_fmt);
+        }
+        if let Some(profile) = &self.profile {
+            encoder.profile = Some(profile.clone());
+        }
+        if let Some(preset) = &self.preset {
+            encoder.preset = Some(preset.clone());
+        }
+        if let Some(gop) = self.gop {
+            encoder.gop = Some(gop);
+        }
+        encoder
+    }
+}
+
+impl ChunkRange {
+    /// 将范围限定在 `0..len` 内
+    fn resolve(self, len: usize) -> Result<Range<usize>> {
+        let (start, end) = (self.start.unwrap_or(0), self.end.unwrap_or(len));
+        if start >= end || end > len {
+            return Err(err_new!(
+                Kind::Other,
+                &format!("invalid chunk range {start}..{end} for {len} chunks")
+            ));
+        }
+        Ok(start..end)
+    }
+}
+
+/// 将进度事件输出到 stderr，终端中在同一行刷新图像块和编码进度，其余事件各占一行
+fn print_progress(event: &Progress) {
+    let terminal = std::io::stderr().is_terminal();
+    // 终端中先清除正在刷新的行
+    let clear = if terminal { "\r\x1b[2K" } else { "" };
+    match event {
+        Progress::ChunkDrawn { index, total } if terminal => {
+            eprint!("{clear}drawing chunk {}/{total}", index + 1);
+        }
+        Progress::Encoding { target, percent } if terminal => {
+            eprint!("{clear}encoding {} {percent:.0}%", target.display());
+        }
+        Progress::ChunkDrawn { .. } | Progress::Encoding { .. } => {}
+        Progress::SegmentDone {
+            target,
+            index,
+            total,
+            reused,
+        } => {
+            let reused = if *reused { " (reused)" } else { "" };
+            eprintln!(
+                "{clear}segment {}/{total} {}{reused}",
+                index + 1,
+                target.display()
+            );
+        }
+        Progress::Concat => eprintln!("{clear}concat"),
+        Progress::Mux => eprintln!("{clear}mux"),
+        Progress::Cleanup => eprintln!("{clear}cleanup"),
+        Progress::Finished => eprintln!("{clear}finished"),
+    }
+}
+
+fn read_json<P, T>(file: P) -> Result<Vec<T>>
+where
+    P: AsRef<Path>,
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let file = File::open(file.as_ref()).map_err(|e| err_new_io!(e))?;
+    serde_json::from_reader(file).map_err(|e| err_new!(Kind::Other, &e.to_string()))
+}
+
+fn parse_range(s: &str) -> std::result::Result<ChunkRange, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("expected `start..end`, got `{s}`"))?;
+    let parse = |v: &str| -> std::result::Result<Option<usize>, String> {
+        if v.is_empty() {
+            Ok(None)
+        } else {
+            v.parse().map(Some).map_err(|e| format!("`{v}`: {e}"))
+        }
+    };
+    Ok(ChunkRange {
+        start: parse(start)?,
+        end: parse(end)?,
+    })
+}
+
+fn parse_screen(s: &str) -> std::result::Result<(u32, u32), String> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected `WIDTHxHEIGHT`, got `{s}`"))?;
+    let w = w.parse().map_err(|e| format!("`{w}`: {e}"))?;
+    let h = h.parse().map_err(|e| format!("`{h}`: {e}"))?;
+    if w == 0 || h == 0 {
+        return Err(format!("screen dimensions must be non-zero, got `{s}`"));
+    }
+    Ok((w, h))
+}
+
+fn parse_fit_width(s: &str) -> std::result::Result<(u32, u32), String> {
+    let (min, max) = s
+        .split_once('-')
+        .ok_or_else(|| format!("expected `MIN-MAX`, got `{s}`"))?;
+    let min = min.parse().map_err(|e| format!("`{min}`: {e}"))?;
+    let max = max.parse().map_err(|e| format!("`{max}`: {e}"))?;
+    if min == 0 || min > max {
+        return Err(format!("expected `0 < MIN <= MAX`, got `{s}`"));
+    }
+    Ok((min, max))
+}
+
+fn parse_color_pair(s: &str) -> std::result::Result<(Rgba<u8>, Rgba<u8>), String> {
+    let (up, down) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected `UP,DOWN`, got `{s}`"))?;
+    Ok((parse_color(up.trim())?, parse_color(down.trim())?))
+}
+
+fn parse_time(s: &str) -> std::result::Result<f64, String> {
+    let v = s.strip_suffix('s').unwrap_or(s);
+    let (minutes, seconds) = match v.rsplit_once(':') {
+        Some((m, sec)) => (m.parse::<u32>().map_err(|e| format!("`{m}`: {e}"))?, sec),
+        None => (0, v),
+    };
+    let seconds: f64 = seconds.parse().map_err(|e| format!("`{seconds}`: {e}"))?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(format!("expected a non-negative time, got `{s}`"));
+    }
+    Ok(f64::from(minutes) * 60.0 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_screen("1080x1920"), Ok((1080, 1920)));
+        assert!(parse_screen("0x1080").is_err());
+        assert_eq!(parse_fit_width("240-720"), Ok((240, 720)));
+        assert!(parse_fit_width("720-240").is_err());
+        assert!(parse_fit_width("0-240").is_err());
+        assert_eq!(
+            parse_color_pair("#1796eb, 2c559980"),
+            Ok((Rgba([23, 150, 235, 255]), Rgba([44, 85, 153, 128])))
+        );
+        assert!(parse_color_pair("#fff,#000").is_err());
+
+        let range = parse_range("10..").unwrap();
+        assert_eq!(range.resolve(60).unwrap(), 10..60);
+        let range = parse_range("..60").unwrap();
+        assert_eq!(range.resolve(100).unwrap(), 0..60);
+        assert!(parse_range("..60").unwrap().resolve(59).is_err());
+        assert!(parse_range("60").is_err());
+
+        assert_eq!(parse_time("12.5s"), Ok(12.5));
+        assert_eq!(parse_time("12.5"), Ok(12.5));
+        assert_eq!(parse_time("1:02.5"), Ok(62.5));
+        assert!(parse_time("-1s").is_err());
+        assert!(parse_time("1m").is_err());
+    }
+}
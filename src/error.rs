@@ -44,6 +44,21 @@ macro_rules! err_new_tryfrom {
     };
 }
 
+#[macro_export]
+macro_rules! err_new_ffmpeg {
+    ($err:expr) => {{
+        let err: $crate::error::FfmpegError = $err;
+        let message = err.to_string();
+        $crate::error::Error::new(
+            file!(),
+            line!(),
+            column!(),
+            $crate::error::Kind::Ffmpeg(Box::new(err)),
+            &message,
+        )
+    }};
+}
+
 pub struct Error {
     location: Option<String>,
     kind: Kind,
@@ -109,6 +124,18 @@ impl Error {
             message: message.to_string(),
         }
     }
+
+    /// 错误类型
+    #[must_use]
+    pub fn kind(&self) -> &Kind {
+        &self.kind
+    }
+
+    /// 错误信息
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 #[derive(Debug)]
@@ -117,6 +144,94 @@ pub enum Kind {
     ImageError,
     InvalidFont,
     BigImgBuilderError,
+    ConfigError,
     TryFromIntError,
+    Ffmpeg(Box<FfmpegError>),
+    FfmpegUnavailable,
+    Cancelled,
     Other,
 }
+
+/// `stderr` 中保留的最大行数
+const STDERR_TAIL_LINES: usize = 40;
+
+/// `FFmpeg` 进程执行失败的详细信息
+///
+/// # Parameters
+///
+/// * `args`: 传给 `FFmpeg` 的完整参数列表。
+/// * `code`: 进程退出码，被信号终止时为 `None`。
+/// * `stderr`: `stderr` 的最后若干行。
+/// * `target`: 正在生成的文件，即最后一个参数。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegError {
+    pub args: Vec<String>,
+    pub code: Option<i32>,
+    pub stderr: String,
+    pub target: Option<String>,
+}
+
+impl FfmpegError {
+    /// 根据参数列表、退出状态和 `stderr` 创建，`stderr` 只保留最后若干行。
+    #[must_use]
+    pub fn new<S: AsRef<std::ffi::OsStr>>(
+        args: &[S],
+        status: std::process::ExitStatus,
+        stderr: &[u8],
+    ) -> Self {
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+            .collect();
+        let stderr = String::from_utf8_lossy(stderr);
+        let lines: Vec<&str> = stderr.lines().collect();
+        Self {
+            target: args.last().cloned(),
+            args,
+            code: status.code(),
+            stderr: lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n"),
+        }
+    }
+}
+
+impl std::fmt::Display for FfmpegError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "FFmpeg exited with code {code}")?,
+            None => write!(f, "FFmpeg was terminated by a signal")?,
+        }
+        if let Some(target) = &self.target {
+            write!(f, " while producing {target}")?;
+        }
+        if let Some(last) = self.stderr.lines().last() {
+            write!(f, ": {last}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ffmpeg_error() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let stderr: String = (0..50).map(|i| format!("line {i}\n")).collect();
+        let err = FfmpegError::new(
+            &["-i", "00.png", "00.mp4"],
+            std::process::ExitStatus::from_raw(1 << 8),
+            stderr.as_bytes(),
+        );
+        assert_eq!(err.code, Some(1));
+        assert_eq!(err.target.as_deref(), Some("00.mp4"));
+        assert_eq!(err.stderr.lines().count(), STDERR_TAIL_LINES);
+        assert!(err.stderr.starts_with("line 10\n"));
+        assert_eq!(
+            err.to_string(),
+            "FFmpeg exited with code 1 while producing 00.mp4: line 49"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 视频的音频轨道
+///
+/// # Parameters
+///
+/// * `path`: 音频文件路径。
+/// * `volume`: 音量倍数，默认为 `1.0`。
+/// * `fade_in`: 淡入时长（秒），默认为 `0`。
+/// * `fade_out`: 淡出时长（秒），默认为 `0`。
+/// * `looped`: 音频比视频短时是否循环播放，默认为 `true`；音频比视频长时总是截断到视频时长。
+/// * `beat`: 音频中节拍标记的时间（秒），设置后该时刻与滑动阶段的开始对齐。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AudioTrack {
+    pub path: PathBuf,
+    #[serde(default = "default_volume")]
+    pub volume: f64,
+    #[serde(default)]
+    pub fade_in: f64,
+    #[serde(default)]
+    pub fade_out: f64,
+    #[serde(default = "default_looped", rename = "loop")]
+    pub looped: bool,
+    #[serde(default)]
+    pub beat: Option<f64>,
+}
+
+fn default_volume() -> f64 {
+    1.0
+}
+
+fn default_looped() -> bool {
+    true
+}
+
+impl AudioTrack {
+    /// 使用默认参数创建音频轨道。
+    ///
+    /// # Parameters
+    /// - `path`: 音频文件路径。
+    ///
+    #[must_use]
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            volume: default_volume(),
+            fade_in: 0.0,
+            fade_out: 0.0,
+            looped: default_looped(),
+            beat: None,
+        }
+    }
+
+    /// 生成该轨道在 `filter_complex` 中的滤镜链。
+    ///
+    /// # Parameters
+    /// - `input`: 音频在 `FFmpeg` 输入中的序号。
+    /// - `scroll_start`: 滑动阶段在视频中开始的时间（秒）。
+    /// - `duration`: 视频总时长（秒）。
+    ///
+    pub(super) fn filter(&self, input: usize, scroll_start: f64, duration: f64) -> String {
+        // 节拍标记与滑动阶段开始对齐：需要延后则补静音，需要提前则裁掉开头
+        let shift = self.beat.map_or(0.0, |beat| scroll_start - beat);
+        let skip = if shift < 0.0 { -shift } else { 0.0 };
+        let mut filters = vec![format!("atrim=start={skip:.3},asetpts=PTS-STARTPTS")];
+        if shift > 0.0 {
+            filters.push(format!("adelay={:.0}:all=1", shift * 1000.0));
+        }
+        filters.push(format!("volume={:.3}", self.volume));
+        if self.fade_in > 0.0 {
+            filters.push(format!(
+                "afade=t=in:st={:.3}:d={:.3}",
+                shift.max(0.0),
+                self.fade_in
+            ));
+        }
+        if self.fade_out > 0.0 {
+            filters.push(format!(
+                "afade=t=out:st={:.3}:d={:.3}",
+                (duration - self.fade_out).max(0.0),
+                self.fade_out
+            ));
+        }
+        filters.push(format!("atrim=end={duration:.3}"));
+        format!("[{input}:a]{}[a{input}]", filters.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let mut track = AudioTrack::new("bgm.mp3");
+        assert_eq!(
+            track.filter(1, 3.0, 60.0),
+            "[1:a]atrim=start=0.000,asetpts=PTS-STARTPTS,volume=1.000,atrim=end=60.000[a1]"
+        );
+
+        // 节拍在音频 1.5 秒处，滑动阶段从 3 秒开始，音频需要延后 1.5 秒
+        track.beat = Some(1.5);
+        track.fade_in = 1.0;
+        track.fade_out = 2.0;
+        track.volume = 0.5;
+        assert_eq!(
+            track.filter(2, 3.0, 60.0),
+            "[2:a]atrim=start=0.000,asetpts=PTS-STARTPTS,adelay=1500:all=1,volume=0.500,\
+             afade=t=in:st=1.500:d=1.000,afade=t=out:st=58.000:d=2.000,atrim=end=60.000[a2]"
+        );
+
+        // 节拍在音频 5 秒处，需要裁掉开头 2 秒
+        track.beat = Some(5.0);
+        assert!(track
+            .filter(1, 3.0, 60.0)
+            .starts_with("[1:a]atrim=start=2.000,asetpts=PTS-STARTPTS,volume=0.500,"));
+    }
+}
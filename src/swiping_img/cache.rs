@@ -0,0 +1,181 @@
+use super::{BigImg, Chunk};
+use crate::{err_new_image, err_new_io, error::Result};
+use image::DynamicImage;
+use std::{
+    collections::HashSet,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// 缓存目录名，位于工作路径中
+const CACHE_DIR: &str = "cache";
+
+/// 临时缓存文件的序号，保证并行写入时文件名不重复
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// `FNV-1a` 64 位哈希，结果与平台和程序版本无关，可以作为磁盘缓存的键
+pub(super) struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl BigImg<'_> {
+    /// 计算影响 `Chunk::draw_data` 结果的样式参数的哈希值，包括字体数据。
+    pub(super) fn style_hash(&self) -> u64 {
+        let mut hasher = Fnv64::default();
+        self.chunk_cross().hash(&mut hasher);
+        self.axis.hash(&mut hasher);
+        self.text_background_color.0 .0.hash(&mut hasher);
+        self.text_background_color.1 .0.hash(&mut hasher);
+        self.text_color.0.hash(&mut hasher);
+        self.max_scale.to_bits().hash(&mut hasher);
+        self.pic_h.hash(&mut hasher);
+        self.text_up_h.hash(&mut hasher);
+        self.layout_scale.to_bits().hash(&mut hasher);
+        self.font.as_slice().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// 图像块的缓存键，由图片文件内容、文本、沿滑动方向的长度 `len` 和样式参数决定。
+    ///
+    /// # Errors
+    /// - 如果图片文件读取失败，则返回 `Err`。
+    ///
+    pub(super) fn cache_key(&self, chunk: &Chunk, len: u32) -> Result<u64> {
+        let mut hasher = Fnv64::default();
+        self.style_hash.hash(&mut hasher);
+        len.hash(&mut hasher);
+        std::fs::read(chunk.pic_path())
+            .map_err(|e| err_new_io!(e))?
+            .hash(&mut hasher);
+        chunk.text_up().hash(&mut hasher);
+        chunk.text_down().hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    fn cache_path(&self, key: u64) -> PathBuf {
+        self.work_dir
+            .join(CACHE_DIR)
+            .join(format!("{key:016x}.png"))
+    }
+
+    /// 绘制图像块，启用缓存时优先读取缓存，未命中时绘制后写入缓存。
+    ///
+    /// # Parameters
+    /// - `chunk`: 要绘制的图像块。
+    /// - `len`: 图像块沿滑动方向的长度。
+    ///
+    /// # Errors
+    /// - 如果绘制图像块或读写缓存时发生错误，则返回 `Err`。
+    ///
+    pub(super) fn draw_cached(&self, chunk: &Chunk, len: u32) -> Result<DynamicImage> {
+        if !self.cache {
+            return chunk.draw_data(self, len);
+        }
+        let path = self.cache_path(self.cache_key(chunk, len)?);
+        if let Ok(image) = image::open(&path) {
+            return Ok(image);
+        }
+
+        let image = chunk.draw_data(self, len)?;
+        std::fs::create_dir_all(path.parent().expect("cache path has a parent"))
+            .map_err(|e| err_new_io!(e))?;
+        // 先写入临时文件再重命名，避免并行绘制相同的图像块时读到不完整的缓存
+        let tmp = path.with_extension(format!(
+            "{}.tmp",
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        image
+            .save_with_format(&tmp, image::ImageFormat::Png)
+            .map_err(|e| err_new_image!(e))?;
+        std::fs::rename(&tmp, &path).map_err(|e| err_new_io!(e))?;
+        Ok(image)
+    }
+
+    /// 删除当前图像块和样式参数不再使用的缓存。
+    ///
+    /// # Results
+    /// 返回删除的缓存文件数量。
+    ///
+    /// # Errors
+    /// - 如果图片文件读取或缓存目录访问失败，则返回 `Err`。
+    ///
+    pub fn prune_cache(&self) -> Result<usize> {
+        let dir = self.work_dir.join(CACHE_DIR);
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let used: HashSet<PathBuf> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| Ok(self.cache_path(self.cache_key(chunk, self.chunk_len(i))?)))
+            .collect::<Result<_>>()?;
+        let mut pruned = 0;
+        for entry in std::fs::read_dir(&dir).map_err(|e| err_new_io!(e))? {
+            let path = entry.map_err(|e| err_new_io!(e))?.path();
+            if !used.contains(&path) {
+                std::fs::remove_file(&path).map_err(|e| err_new_io!(e))?;
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swiping_img::test_chunks;
+
+    #[test]
+    fn test_fnv() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv64::default();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(hash(b"ab"), hash(b"ba"));
+    }
+
+    #[test]
+    fn test_prune_cache_without_cache() {
+        let chunks = test_chunks(&["a"]);
+        let work_dir = std::env::temp_dir().join(format!("to_video_prune_{}", std::process::id()));
+        std::fs::create_dir_all(work_dir.join(CACHE_DIR)).unwrap();
+        let si = BigImg::builder(&work_dir, &chunks).build().unwrap();
+        let used = si.cache_path(si.cache_key(&chunks[0], si.chunk_len(0)).unwrap());
+        let stale = si.cache_path(0);
+        std::fs::write(&used, b"").unwrap();
+        std::fs::write(&stale, b"").unwrap();
+
+        // 禁用缓存时缓存键不变，只删除不再使用的缓存
+        let si = BigImg::builder(&work_dir, &chunks)
+            .cache(false)
+            .build()
+            .unwrap();
+        assert_eq!(si.prune_cache().unwrap(), 1);
+        assert!(used.exists());
+        assert!(!stale.exists());
+        std::fs::remove_dir_all(&work_dir).unwrap();
+    }
+}
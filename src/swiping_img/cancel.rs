@@ -0,0 +1,64 @@
+use super::BigImg;
+use crate::{
+    err_new,
+    error::{Kind, Result},
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// 取消令牌
+///
+/// 克隆得到的令牌共享同一个状态，在任意线程中调用 [`CancelToken::cancel`] 后，
+/// 正在生成的视频会在下一个检查点停止：绘制图像块、生成分段视频和逐帧编码前都会检查，
+/// 正在运行的 `FFmpeg` 进程会被终止，未完成的输出文件会被删除。
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// 创建一个未取消的令牌
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取消使用该令牌的所有任务
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// 是否已经取消
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl BigImg<'_> {
+    /// 检查是否已经取消。
+    ///
+    /// # Errors
+    /// - 如果已经取消，则返回 `Cancelled` 类型错误。
+    ///
+    pub(super) fn check_cancelled(&self) -> Result<()> {
+        if self.cancel.is_cancelled() {
+            return Err(err_new!(Kind::Cancelled, "render cancelled"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_token() {
+        let token = CancelToken::new();
+        let shared = token.clone();
+        assert!(!shared.is_cancelled());
+        std::thread::spawn(move || token.cancel()).join().unwrap();
+        assert!(shared.is_cancelled());
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
 use image::{DynamicImage, GenericImage, GenericImageView};
 use imageproc::{drawing, rect::Rect};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // 定义 Chunk 结构体
 #[derive(Serialize, Deserialize)]
@@ -57,11 +57,49 @@ impl Chunk {
         })
     }
 
+    /// 获取图片文件的路径
+    #[must_use]
+    pub fn pic_path(&self) -> &Path {
+        &self.pic_path
+    }
+
+    /// 获取上方文本
+    #[must_use]
+    pub fn text_up(&self) -> &[String] {
+        &self.text_up
+    }
+
+    /// 获取下方文本
+    #[must_use]
+    pub fn text_down(&self) -> &[String] {
+        &self.text_down
+    }
+
+    /// 校验 Chunk 数据是否可以被绘制
+    ///
+    /// # Errors
+    ///
+    /// * `IoError(NotFound)` - 如果图片路径不存在
+    /// * `Other` - 如果上方或下方文本为空
+    pub fn check(&self) -> Result<()> {
+        if !self.pic_path.exists() {
+            return Err(err_new!(
+                Kind::IoError(std::io::ErrorKind::NotFound),
+                &format!("pic_path is not exist: {}", self.pic_path.display())
+            ));
+        }
+        if self.text_up.is_empty() || self.text_down.is_empty() {
+            return Err(err_new!(Kind::Other, "text_up or text_down is empty"));
+        }
+        Ok(())
+    }
+
     /// 绘制 Chunk 数据到一个图像上
     ///
     /// # Parameters
     ///
     /// * `si` - 包含屏幕信息和样式的大图像实例
+    /// * `chunk_len` - 图像块沿滑动方向的长度
     ///
     /// # Returns
     ///
@@ -71,11 +109,10 @@ impl Chunk {
     ///
     /// * `ImageError` - 如果打开或处理图片时发生错误
     /// * `TryFromIntError` - 如果在类型转换过程中发生溢出或其他错误
-    pub fn draw_data(&self, si: &BigImg) -> Result<DynamicImage> {
+    pub fn draw_data(&self, si: &BigImg, chunk_len: u32) -> Result<DynamicImage> {
         // 解构 BigImg 实例，获取所需的字段
         let BigImg {
-            screen,
-            width_chunk,
+            axis,
             text_background_color,
             text_color,
             max_scale,
@@ -83,66 +120,80 @@ impl Chunk {
             text_up_h,
             text_down_h,
             font,
+            layout_scale,
             ..
         } = si;
+        // 边距、圆角等固定尺寸随版式缩放
+        let px = |v: u32| (v as f32 * layout_scale).round() as u32;
+        let margin = px(10);
+        // 图片和上下文本沿垂直于滑动方向排列，`start` 和 `len` 为区域在该方向上的位置和长度，
+        // 返回区域的 `(x, y, 宽度, 高度)`；`skip` 为区域沿滑动方向跳过的像素数
+        let region = |start: u32, len: u32, skip: u32| {
+            let (x, y) = axis.size(skip, start);
+            let (w, h) = axis.size(chunk_len - skip, len);
+            (x, y, w, h)
+        };
 
         // 创建一个新的 `DynamicImage` 实例作为绘制目标
-        let mut target = DynamicImage::new_rgba8(*width_chunk, screen.1);
+        let (chunk_w, chunk_h) = axis.size(chunk_len, si.chunk_cross());
+        let mut target = DynamicImage::new_rgba8(chunk_w, chunk_h);
 
         // 打开并调整图片大小
+        let (pic_x, pic_y, area_w, area_h) = region(0, *pic_h, 0);
         let img = image::open(&self.pic_path)
             .map_err(|e| err_new_image!(e))?
-            .thumbnail(*width_chunk, *pic_h);
+            .thumbnail(area_w, area_h);
         let (img_w, img_h) = img.dimensions();
-        // 将调整好大小的图片复制到目标图像的中心位置
+        // 将调整好大小的图片复制到图片区域的中心位置
         target
-            .copy_from(&img, (width_chunk - img_w) / 2, (pic_h - img_h) / 2)
+            .copy_from(
+                &img,
+                pic_x + (area_w - img_w) / 2,
+                pic_y + (area_h - img_h) / 2,
+            )
             .map_err(|e| err_new_image!(e))?;
 
-        // 绘制上下文本的背景框
-        let text_up_rect = Rect::at(1, i32::try_from(*pic_h)?).of_size(width_chunk - 1, *text_up_h);
-        let text_down_rect =
-            Rect::at(1, i32::try_from(pic_h + text_up_h)?).of_size(width_chunk - 1, *text_down_h);
-        target.draw_filled_rounded_rect(text_up_rect, 10, text_background_color.0);
-        target.draw_filled_rounded_rect(text_down_rect, 10, text_background_color.1);
-
-        // 获取上下文本的内容和长度
-        let (text_up, text_down) = (&self.text_up, &self.text_down);
-        let (len_up, len_down) = (text_up.len(), text_down.len());
-        let (h_up, h_down) = (
-            text_up_h / u32::try_from(len_up)?,
-            (text_down_h - 30) / u32::try_from(len_down)?,
-        );
-
-        // 绘制上文本
-        for (i, str) in text_up.iter().enumerate() {
-            let high = pic_h + u32::try_from(i)? * h_up;
-            target.text_center(
-                *text_color,
-                Rect::at(10, i32::try_from(high)?).of_size(width_chunk - 20, h_up),
-                *max_scale,
-                &font,
-                str,
-            );
+        // 绘制上下文本的背景框，沿滑动方向留出 1 像素显示分割线
+        let text_up_region = region(*pic_h, *text_up_h, 0);
+        let text_down_region = region(pic_h + text_up_h, *text_down_h, 0);
+        let radius = i32::try_from(margin.max(1))?;
+        for ((x, y, w, h), color) in [
+            (region(*pic_h, *text_up_h, 1), text_background_color.0),
+            (
+                region(pic_h + text_up_h, *text_down_h, 1),
+                text_background_color.1,
+            ),
+        ] {
+            let rect = Rect::at(i32::try_from(x)?, i32::try_from(y)?).of_size(w, h);
+            target.draw_filled_rounded_rect(rect, radius, color);
         }
 
-        // 绘制下文本
-        for (i, str) in text_down.iter().enumerate() {
-            let high = pic_h + text_up_h + u32::try_from(i)? * h_down;
-            target.text_center(
-                *text_color,
-                Rect::at(10, i32::try_from(high)?).of_size(width_chunk - 20, h_down),
-                *max_scale,
-                &font,
-                str,
-            );
+        // 绘制上下文本，每个字符串一行，从上到下排列，下方文本底部留出空白
+        for ((x, y, w, h), texts, reserve) in [
+            (text_up_region, &self.text_up, 0),
+            (text_down_region, &self.text_down, px(30)),
+        ] {
+            let row_h = (h - reserve) / u32::try_from(texts.len())?;
+            for (i, str) in texts.iter().enumerate() {
+                let high = y + u32::try_from(i)? * row_h;
+                target.text_center(
+                    *text_color,
+                    Rect::at(i32::try_from(x + margin)?, i32::try_from(high)?)
+                        .of_size(w - 2 * margin, row_h),
+                    *max_scale,
+                    &font,
+                    str,
+                );
+            }
         }
 
         // 绘制分割线
+        let (start_x, start_y) = axis.size(0, margin);
+        let (end_x, end_y) = axis.size(0, axis.split((chunk_w, chunk_h)).1);
         drawing::draw_line_segment_mut(
             &mut target,
-            (0.0, 10.0),
-            (0.0, screen.1 as f32),
+            (start_x as f32, start_y as f32),
+            (end_x as f32, end_y as f32),
             *text_color,
         );
 
@@ -0,0 +1,479 @@
+use super::{
+    load_font, AudioTrack, Axis, BigImgBuilder, Direction, EncoderSettings, MotionProfile,
+    OutputFormat, Pipeline, SwipSpeed,
+};
+use crate::{
+    err_new, err_new_io,
+    error::{Kind, Result},
+};
+use image::Rgba;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+/// 项目文件中可以继承的内置预设
+const PRESETS: [&str; 3] = ["1080p-landscape", "720p-landscape", "vertical-shorts"];
+
+/// 项目文件中 `preset` 链的最大深度，防止循环继承
+const MAX_PRESET_DEPTH: usize = 8;
+
+/// 视频项目配置
+///
+/// 对应 `BigImgBuilder` 的全部可配置参数，未给出的参数保持 `BigImgBuilder` 的默认值。
+/// 可以从 TOML 或 JSON 项目文件加载，见 [`BigImgConfig::load`]。
+///
+/// # Parameters
+///
+/// * `screen`: 屏幕分辨率，例如 `[1920, 1080]`。
+/// * `step`: 每次处理图像块的列数。
+/// * `width_chunk`: 每个图像块沿滑动方向的长度。
+/// * `fit_width`: 图像块沿滑动方向的长度范围，例如 `[240, 720]`，给出时长度随图片的宽高比变化。
+/// * `axis`: 滑动方向，`"horizontal"` 或 `"vertical"`。
+/// * `rows`: 每列图像块的数量。
+/// * `text_background_color`: 上下文本的背景颜色，例如 `["#1796eb", "#2c5599"]`。
+/// * `text_color`: 文本颜色，例如 `"#ffffff"`。
+/// * `max_scale`: 字体的最大缩放因子。
+/// * `pic_h`: 图像块中的图片区域高度。
+/// * `text_up_h`: 图像块中的上方文本的高度。
+/// * `font`: 字体文件路径，相对路径以项目文件所在目录为基准。
+/// * `video_cover_time`: 视频封面图像的持续时间。
+/// * `video_ending_time`: 视频结束图像的持续时间。
+/// * `video_background_color`: 视频的背景颜色。
+/// * `video_swip_speed`: 视频滑动一个图像块所需的秒数，例如 `2.5`，或每秒滑动的像素数，例如 `"300px/s"`。
+/// * `video_fps`: 视频的帧率。
+/// * `pipeline`: 视频生成方式，`"segments"` 或 `"stream"`。
+/// * `motion`: 滑动阶段的运动曲线，例如 `{ type = "ease", ease_in = 1.0, ease_out = 1.0 }`。
+/// * `direction`: 滑动方向，`"forward"`、`"reverse"` 或 `"ping-pong"`。
+/// * `audio`: 音频轨道列表，例如 `[{ path = "bgm.mp3", volume = 0.8, fade_out = 2.0 }]`，
+///   相对路径以项目文件所在目录为基准。
+/// * `subtitles`: 是否生成 `SRT` 和 `WebVTT` 字幕文件。
+/// * `chapters`: 是否在视频中写入章节。
+/// * `output_format`: 输出格式，例如 `{ type = "gif", colors = 128 }`，未给出时根据输出文件的扩展名推断。
+/// * `encoder`: 视频编码参数，例如 `{ codec = "libx265", crf = 24, preset = "slow" }`，
+///   未给出的编码参数保持默认值。
+/// * `workers`: 并行绘制图像块的线程数。
+/// * `cache`: 是否缓存绘制好的图像块并复用未改变的分段视频。
+/// * `ffmpeg`: `FFmpeg` 可执行文件的路径，包含目录时以项目文件所在目录为基准，只有文件名时在 `PATH` 中查找。
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BigImgConfig {
+    pub screen: Option<(u32, u32)>,
+    pub step: Option<u32>,
+    pub width_chunk: Option<u32>,
+    pub fit_width: Option<(u32, u32)>,
+    pub axis: Option<Axis>,
+    pub rows: Option<u32>,
+    pub text_background_color: Option<(HexColor, HexColor)>,
+    pub text_color: Option<HexColor>,
+    pub max_scale: Option<f32>,
+    pub pic_h: Option<u32>,
+    pub text_up_h: Option<u32>,
+    pub font: Option<PathBuf>,
+    pub video_cover_time: Option<u32>,
+    pub video_ending_time: Option<u32>,
+    pub video_background_color: Option<String>,
+    pub video_swip_speed: Option<SwipSpeed>,
+    pub video_fps: Option<u32>,
+    pub pipeline: Option<Pipeline>,
+    pub motion: Option<MotionProfile>,
+    pub direction: Option<Direction>,
+    pub audio: Option<Vec<AudioTrack>>,
+    pub subtitles: Option<bool>,
+    pub chapters: Option<bool>,
+    pub output_format: Option<OutputFormat>,
+    pub encoder: Option<EncoderSettings>,
+    pub workers: Option<usize>,
+    pub cache: Option<bool>,
+    pub ffmpeg: Option<PathBuf>,
+}
+
+/// 以 `#RRGGBB` 或 `#RRGGBBAA` 字符串表示的颜色
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor(pub Rgba<u8>);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        parse_color(&value).map(HexColor)
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(value: HexColor) -> Self {
+        let [r, g, b, a] = value.0 .0;
+        if a == 255 {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        }
+    }
+}
+
+/// 解析 `#RRGGBB` 或 `#RRGGBBAA` 格式的颜色，`#` 可以省略
+///
+/// # Errors
+/// - 如果格式不正确，则返回描述错误的字符串。
+///
+pub fn parse_color(s: &str) -> std::result::Result<Rgba<u8>, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return Err(format!("expected `#RRGGBB` or `#RRGGBBAA`, got `{s}`"));
+    }
+    let mut rgba = [255; 4];
+    for (i, v) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+        *v = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|e| format!("`{s}`: {e}"))?;
+    }
+    Ok(Rgba(rgba))
+}
+
+impl BigImgConfig {
+    /// 从项目文件加载配置。
+    ///
+    /// 根据扩展名选择格式：`.toml` 为 TOML，其余按 JSON 解析。
+    /// 项目文件可以用 `preset = "名称"` 继承一个预设，并覆盖其中的任意参数。
+    /// 预设可以是内置预设（`1080p-landscape`、`720p-landscape`、`vertical-shorts`），
+    /// 也可以是同一文件中 `[presets.名称]` 表定义的预设，后者同样可以继续继承。
+    ///
+    /// # Parameters
+    /// - `path`: 项目文件路径。
+    ///
+    /// # Errors
+    /// - 如果文件读取或解析失败，则返回 `Err`。
+    /// - 如果存在未知参数或参数值无效，则返回 `ConfigError`，并列出所有出错的参数。
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| err_new_io!(e))?;
+        let value = if path.extension().is_some_and(|ext| ext == "toml") {
+            let value: toml::Value =
+                toml::from_str(&text).map_err(|e| err_new!(Kind::ConfigError, &e.to_string()))?;
+            serde_json::to_value(value).map_err(|e| err_new!(Kind::ConfigError, &e.to_string()))?
+        } else {
+            serde_json::from_str(&text).map_err(|e| err_new!(Kind::ConfigError, &e.to_string()))?
+        };
+        let mut config = Self::from_value(value)
+            .map_err(|e| err_new!(Kind::ConfigError, &format!("{}: {e}", path.display())))?;
+
+        // 字体、音频和 FFmpeg 路径以项目文件所在目录为基准
+        if let Some(dir) = path.parent() {
+            if let Some(font) = &mut config.font {
+                *font = dir.join(&*font);
+            }
+            for track in config.audio.iter_mut().flatten() {
+                track.path = dir.join(&track.path);
+            }
+            if let Some(ffmpeg) = config
+                .ffmpeg
+                .as_mut()
+                .filter(|p| p.components().count() > 1)
+            {
+                *ffmpeg = dir.join(&*ffmpeg);
+            }
+        }
+        Ok(config)
+    }
+
+    /// 从已解析的项目文件内容中解析配置，处理 `preset` 继承。
+    ///
+    /// # Errors
+    /// - 如果存在未知参数、参数值无效或预设无法解析，则返回描述所有错误的字符串。
+    ///
+    fn from_value(value: Value) -> std::result::Result<Self, String> {
+        let Value::Object(mut project) = value else {
+            return Err("project file must be a table".to_string());
+        };
+        let presets = match project.remove("presets") {
+            None => Map::new(),
+            Some(Value::Object(presets)) => presets,
+            Some(_) => return Err("`presets` must be a table".to_string()),
+        };
+
+        let merged = resolve_preset(project, &presets, 0)?;
+
+        // 逐个参数反序列化，以便一次报告所有未知或无效的参数
+        let errors: Vec<String> = merged
+            .iter()
+            .filter_map(|(key, value)| {
+                let single = Map::from_iter([(key.clone(), value.clone())]);
+                serde_json::from_value::<Self>(Value::Object(single))
+                    .map_err(|e| e.to_string())
+                    .and_then(|config| config.check())
+                    .err()
+                    .map(|e| format!("`{key}`: {e}"))
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors.join("; "));
+        }
+        serde_json::from_value(Value::Object(merged)).map_err(|e| e.to_string())
+    }
+
+    /// 检查 `BigImgBuilder` 的设置方法要求非零或有序的参数。
+    ///
+    /// # Errors
+    /// - 如果屏幕尺寸、`step`、`width_chunk`、`rows`、`pic_h`、`text_up_h` 或 `workers` 为零，
+    ///   或者 `fit_width` 不满足 `0 < 最小值 <= 最大值`，则返回描述错误的字符串。
+    ///
+    fn check(&self) -> std::result::Result<(), String> {
+        if self.screen.is_some_and(|(w, h)| w == 0 || h == 0) {
+            return Err("screen dimensions must be non-zero".to_string());
+        }
+        for (value, name) in [
+            (self.step, "step"),
+            (self.width_chunk, "width_chunk"),
+            (self.rows, "rows"),
+            (self.pic_h, "pic_h"),
+            (self.text_up_h, "text_up_h"),
+            (self.video_fps, "video_fps"),
+        ] {
+            if value == Some(0) {
+                return Err(format!("{name} must be non-zero"));
+            }
+        }
+        if self
+            .fit_width
+            .is_some_and(|(min, max)| min == 0 || min > max)
+        {
+            return Err("fit_width must satisfy 0 < min <= max".to_string());
+        }
+        if self.workers == Some(0) {
+            return Err("workers must be non-zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// 将配置写入 `BigImgBuilder`，未给出的参数保持不变。
+    ///
+    /// # Errors
+    /// - 如果字体加载失败，则返回 `Err`。
+    ///
+    pub fn apply(&self, builder: &mut BigImgBuilder) -> Result<()> {
+        if let Some(screen) = self.screen {
+            builder.screen(screen);
+        }
+        if let Some(step) = self.step {
+            builder.step(step);
+        }
+        if let Some(width_chunk) = self.width_chunk {
+            builder.width_chunk(width_chunk);
+        }
+        if let Some((min, max)) = self.fit_width {
+            builder.fit_width(min, max);
+        }
+        if let Some(axis) = self.axis {
+            builder.axis(axis);
+        }
+        if let Some(rows) = self.rows {
+            builder.rows(rows);
+        }
+        if let Some((up, down)) = self.text_background_color {
+            builder.text_background_color((up.0, down.0));
+        }
+        if let Some(color) = self.text_color {
+            builder.text_color(color.0);
+        }
+        if let Some(max_scale) = self.max_scale {
+            builder.max_scale(max_scale);
+        }
+        if let Some(pic_h) = self.pic_h {
+            builder.pic_h(pic_h);
+        }
+        if let Some(text_up_h) = self.text_up_h {
+            builder.text_up_h(text_up_h);
+        }
+        if let Some(font) = &self.font {
+            builder.font(load_font(font)?);
+        }
+        if let Some(time) = self.video_cover_time {
+            builder.video_cover_time(time);
+        }
+        if let Some(time) = self.video_ending_time {
+            builder.video_ending_time(time);
+        }
+        if let Some(color) = &self.video_background_color {
+            builder.video_background_color(color.clone());
+        }
+        if let Some(speed) = self.video_swip_speed {
+            builder.video_swip_speed(speed);
+        }
+        if let Some(fps) = self.video_fps {
+            builder.video_fps(fps);
+        }
+        if let Some(pipeline) = self.pipeline {
+            builder.pipeline(pipeline);
+        }
+        if let Some(motion) = self.motion {
+            builder.motion(motion);
+        }
+        if let Some(direction) = self.direction {
+            builder.direction(direction);
+        }
+        for track in self.audio.iter().flatten() {
+            builder.audio(track.clone());
+        }
+        if let Some(subtitles) = self.subtitles {
+            builder.subtitles(subtitles);
+        }
+        if let Some(chapters) = self.chapters {
+            builder.chapters(chapters);
+        }
+        if let Some(format) = self.output_format {
+            builder.output_format(format);
+        }
+        if let Some(encoder) = &self.encoder {
+            builder.encoder(encoder.clone());
+        }
+        if let Some(workers) = self.workers {
+            builder.workers(workers);
+        }
+        if let Some(cache) = self.cache {
+            builder.cache(cache);
+        }
+        if let Some(ffmpeg) = &self.ffmpeg {
+            builder.ffmpeg(ffmpeg.clone());
+        }
+        Ok(())
+    }
+}
+
+/// 展开 `table` 中的 `preset` 继承链，返回合并后的参数表，`table` 中的参数优先。
+fn resolve_preset(
+    mut table: Map<String, Value>,
+    presets: &Map<String, Value>,
+    depth: usize,
+) -> std::result::Result<Map<String, Value>, String> {
+    let name = match table.remove("preset") {
+        None => return Ok(table),
+        Some(Value::String(name)) => name,
+        Some(_) => return Err("`preset` must be a string".to_string()),
+    };
+    if depth >= MAX_PRESET_DEPTH {
+        return Err(format!("preset `{name}` is nested too deeply"));
+    }
+
+    let mut base = match (presets.get(&name), builtin_preset(&name)) {
+        (Some(Value::Object(preset)), _) => resolve_preset(preset.clone(), presets, depth + 1)?,
+        (Some(_), _) => return Err(format!("preset `{name}` must be a table")),
+        (None, Some(preset)) => preset,
+        (None, None) => {
+            return Err(format!(
+                "unknown preset `{name}`, expected one of {PRESETS:?} or a `[presets.{name}]` table"
+            ))
+        }
+    };
+    base.extend(table);
+    Ok(base)
+}
+
+/// 获取内置预设的参数表
+fn builtin_preset(name: &str) -> Option<Map<String, Value>> {
+    let preset = match name {
+        "1080p-landscape" => json!({
+            "screen": [1920, 1080],
+            "width_chunk": 480,
+            "pic_h": 520,
+            "text_up_h": 214,
+            "max_scale": 120.0,
+            "video_fps": 60,
+        }),
+        "720p-landscape" => json!({
+            "screen": [1280, 720],
+            "width_chunk": 320,
+            "pic_h": 346,
+            "text_up_h": 142,
+            "max_scale": 80.0,
+            "video_fps": 30,
+        }),
+        "vertical-shorts" => json!({
+            "screen": [1080, 1920],
+            "axis": "vertical",
+            "width_chunk": 480,
+            "pic_h": 640,
+            "text_up_h": 200,
+            "max_scale": 100.0,
+            "video_fps": 60,
+        }),
+        _ => return None,
+    };
+    match preset {
+        Value::Object(preset) => Some(preset),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swiping_img::{test_builder, test_chunks};
+
+    #[test]
+    fn test_preset_override() {
+        let value: toml::Value = toml::from_str(
+            r##"
+            preset = "mine"
+            video_fps = 24
+            text_color = "#000000"
+
+            [presets.mine]
+            preset = "vertical-shorts"
+            step = 10
+            video_fps = 30
+            "##,
+        )
+        .unwrap();
+        let config = BigImgConfig::from_value(serde_json::to_value(value).unwrap()).unwrap();
+        assert_eq!(config.screen, Some((1080, 1920)));
+        assert_eq!(config.step, Some(10));
+        assert_eq!(config.axis, Some(Axis::Vertical));
+        assert_eq!(config.video_fps, Some(24));
+        assert_eq!(config.text_color, Some(HexColor(Rgba([0, 0, 0, 255]))));
+        assert_eq!(config.video_cover_time, None);
+
+        // 预设的尺寸适合纵向滑动的屏幕
+        let chunks = test_chunks(&["a"]);
+        let mut builder = test_builder(&chunks);
+        config.apply(&mut builder).unwrap();
+        builder.build().unwrap();
+    }
+
+    #[test]
+    fn test_report_all_errors() {
+        let value = json!({
+            "screen": [1920],
+            "scren": [1920, 1080],
+            "text_color": "white",
+            "step": 5,
+        });
+        let err = BigImgConfig::from_value(value).unwrap_err();
+        assert!(err.contains("`screen`"), "{err}");
+        assert!(err.contains("`scren`"), "{err}");
+        assert!(err.contains("`text_color`"), "{err}");
+        assert!(!err.contains("`step`:"), "{err}");
+
+        let err =
+            BigImgConfig::from_value(json!({ "fit_width": [0, 240], "workers": 0 })).unwrap_err();
+        assert!(err.contains("`fit_width`: fit_width must satisfy"), "{err}");
+        assert!(err.contains("`workers`: workers must be non-zero"), "{err}");
+
+        let err = BigImgConfig::from_value(json!({ "preset": "4k" })).unwrap_err();
+        assert!(err.contains("unknown preset `4k`"), "{err}");
+        let cycle = json!({ "preset": "a", "presets": { "a": { "preset": "a" } } });
+        assert!(BigImgConfig::from_value(cycle).is_err());
+    }
+
+    #[test]
+    fn test_load_invalid_value() {
+        let path = std::env::temp_dir().join(format!("to_video_step_{}.toml", std::process::id()));
+        std::fs::write(&path, "step = 0\nscreen = [0, 1080]\n").unwrap();
+        let err = BigImgConfig::load(&path).unwrap_err();
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(err.kind(), Kind::ConfigError));
+        assert!(
+            err.message().contains("`step`: step must be non-zero"),
+            "{err}"
+        );
+        assert!(err.message().contains("`screen`"), "{err}");
+    }
+}
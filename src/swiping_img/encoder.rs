@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// 视频编码器
+///
+/// * `Libx264`: `H.264`。
+/// * `Libx265`: `H.265`。
+/// * `LibvpxVp9`: `VP9`。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VideoCodec {
+    #[default]
+    Libx264,
+    Libx265,
+    LibvpxVp9,
+}
+
+impl VideoCodec {
+    /// `FFmpeg` 中的编码器名称
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Libx264 => "libx264",
+            Self::Libx265 => "libx265",
+            Self::LibvpxVp9 => "libvpx-vp9",
+        }
+    }
+
+    /// `crf` 的最大值
+    fn max_crf(self) -> u8 {
+        match self {
+            Self::Libx264 | Self::Libx265 => 51,
+            Self::LibvpxVp9 => 63,
+        }
+    }
+}
+
+impl Display for VideoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for VideoCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "libx264" => Ok(Self::Libx264),
+            "libx265" => Ok(Self::Libx265),
+            "libvpx-vp9" => Ok(Self::LibvpxVp9),
+            _ => Err(format!(
+                "expected `libx264`, `libx265` or `libvpx-vp9`, got `{s}`"
+            )),
+        }
+    }
+}
+
+/// 视频编码参数
+///
+/// 分段视频、封面和结尾视频以及逐帧生成的视频都使用同一组参数编码，
+/// 保证分段视频可以直接以 `-c copy` 合并。
+///
+/// # Parameters
+///
+/// * `codec`: 视频编码器，默认为 `libx264`。
+/// * `crf`: 恒定质量因子，越小质量越高，未设置时使用编码器的默认值。
+/// * `bitrate`: 目标码率，例如 `"4M"`，未设置时由 `crf` 控制质量。
+/// * `pix_fmt`: 像素格式，默认为 `yuv420p`。
+/// * `profile`: 编码档次，例如 `"high"`。
+/// * `preset`: 编码速度预设，默认为 `fast`，`VP9` 编码器忽略该参数。
+/// * `gop`: 关键帧间隔（帧数）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncoderSettings {
+    pub codec: VideoCodec,
+    pub crf: Option<u8>,
+    pub bitrate: Option<String>,
+    pub pix_fmt: String,
+    pub profile: Option<String>,
+    pub preset: Option<String>,
+    pub gop: Option<u32>,
+}
+
+impl Default for EncoderSettings {
+    fn default() -> Self {
+        Self {
+            codec: VideoCodec::default(),
+            crf: None,
+            bitrate: None,
+            pix_fmt: String::from("yuv420p"),
+            profile: None,
+            preset: Some(String::from("fast")),
+            gop: None,
+        }
+    }
+}
+
+impl EncoderSettings {
+    /// 生成 `FFmpeg` 的视频编码参数
+    #[must_use]
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-c:v".to_string(),
+            self.codec.name().to_string(),
+            "-pix_fmt".to_string(),
+            self.pix_fmt.clone(),
+        ];
+        if let Some(crf) = self.crf {
+            args.extend(["-crf".to_string(), crf.to_string()]);
+        }
+        match &self.bitrate {
+            Some(bitrate) => args.extend(["-b:v".to_string(), bitrate.clone()]),
+            // VP9 只有码率为 0 时才使用恒定质量模式
+            None if self.codec == VideoCodec::LibvpxVp9 && self.crf.is_some() => {
+                args.extend(["-b:v".to_string(), "0".to_string()]);
+            }
+            None => {}
+        }
+        if let Some(profile) = &self.profile {
+            args.extend(["-profile:v".to_string(), profile.clone()]);
+        }
+        if let (Some(preset), false) = (&self.preset, self.codec == VideoCodec::LibvpxVp9) {
+            args.extend(["-preset".to_string(), preset.clone()]);
+        }
+        if let Some(gop) = self.gop {
+            args.extend(["-g".to_string(), gop.to_string()]);
+        }
+        args
+    }
+
+    /// 检查编码参数是否有效
+    pub(super) fn check(&self) -> std::result::Result<(), String> {
+        if let Some(crf) = self.crf.filter(|&crf| crf > self.codec.max_crf()) {
+            return Err(format!(
+                "crf of {} must be in 0..={}, got {crf}",
+                self.codec,
+                self.codec.max_crf()
+            ));
+        }
+        if self.pix_fmt.is_empty() {
+            return Err("pix_fmt must not be empty".to_string());
+        }
+        if self.gop == Some(0) {
+            return Err("gop must be non-zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        assert_eq!(
+            EncoderSettings::default().args(),
+            ["-c:v", "libx264", "-pix_fmt", "yuv420p", "-preset", "fast"]
+        );
+
+        let settings: EncoderSettings =
+            serde_json::from_str(r#"{"codec":"libvpx-vp9","crf":31,"gop":120}"#).unwrap();
+        assert_eq!(
+            settings.args(),
+            [
+                "-c:v",
+                "libvpx-vp9",
+                "-pix_fmt",
+                "yuv420p",
+                "-crf",
+                "31",
+                "-b:v",
+                "0",
+                "-g",
+                "120"
+            ]
+        );
+        assert!(settings.check().is_ok());
+
+        let settings = EncoderSettings {
+            crf: Some(60),
+            ..EncoderSettings::default()
+        };
+        assert!(settings.check().is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use super::{BigImg, OutputFormat, Pipeline, VideoCodec};
+use crate::{
+    err_new,
+    error::{Kind, Result},
+};
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// 未设置 `FFmpeg` 路径时读取的环境变量
+pub const FFMPEG_ENV: &str = "FFMPEG_PATH";
+
+/// 支持的最低 `FFmpeg` 版本，`amix` 滤镜的 `normalize` 参数从 4.4 开始提供
+const MIN_VERSION: (u32, u32) = (4, 4);
+
+/// 预检查发现的 `FFmpeg` 信息
+///
+/// # Parameters
+///
+/// * `path`: 实际使用的 `FFmpeg` 可执行文件。
+/// * `version`: `ffmpeg -version` 输出的版本号，例如 `6.1.1-3ubuntu5`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegInfo {
+    pub path: PathBuf,
+    pub version: String,
+}
+
+/// `FFmpeg` 的组件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Component {
+    Filter,
+    Encoder,
+    Demuxer,
+}
+
+impl Component {
+    /// 列出该类组件的命令行参数
+    fn list_arg(self) -> &'static str {
+        match self {
+            Self::Filter => "-filters",
+            Self::Encoder => "-encoders",
+            Self::Demuxer => "-demuxers",
+        }
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Filter => "filter",
+            Self::Encoder => "encoder",
+            Self::Demuxer => "demuxer",
+        })
+    }
+}
+
+impl BigImg<'_> {
+    /// 检查 `FFmpeg` 是否可用，在生成任何文件之前调用，以便尽早给出明确的错误。
+    ///
+    /// 依次检查可执行文件能否找到、版本是否不低于 4.4，
+    /// 以及生成 `save_name` 需要的滤镜、编码器和分离器是否都已编译进 `FFmpeg`。
+    ///
+    /// # Parameters
+    /// - `save_name`: 最终视频文件名，用于推断输出格式。
+    ///
+    /// # Results
+    /// 返回实际使用的 `FFmpeg` 路径和版本号。
+    ///
+    /// # Errors
+    /// - 如果找不到 `FFmpeg`、版本过低或缺少需要的组件，则返回 `Err`。
+    ///
+    pub fn preflight<P: AsRef<Path>>(&self, save_name: P) -> Result<FfmpegInfo> {
+        let path = find_program(&self.ffmpeg).ok_or_else(|| {
+            err_new!(
+                Kind::FfmpegUnavailable,
+                &format!(
+                    "`{}` not found, install FFmpeg or set its path with --ffmpeg or {FFMPEG_ENV}",
+                    self.ffmpeg.display()
+                )
+            )
+        })?;
+        let version_output = query(&path, "-version")?;
+        let version = version_output
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("ffmpeg version "))
+            .and_then(|rest| rest.split_whitespace().next())
+            .unwrap_or("unknown")
+            .to_string();
+        if let Some(found) = parse_version(&version).filter(|&found| found < MIN_VERSION) {
+            return Err(err_new!(
+                Kind::FfmpegUnavailable,
+                &format!(
+                    "{} is version {}.{}, at least {}.{} is required",
+                    path.display(),
+                    found.0,
+                    found.1,
+                    MIN_VERSION.0,
+                    MIN_VERSION.1
+                )
+            ));
+        }
+
+        let required = self.required_components(self.resolve_format(save_name.as_ref()));
+        let mut missing = Vec::new();
+        for component in [Component::Filter, Component::Encoder, Component::Demuxer] {
+            let names: Vec<_> = required
+                .iter()
+                .filter(|(c, _)| *c == component)
+                .map(|(_, name)| *name)
+                .collect();
+            if names.is_empty() {
+                continue;
+            }
+            let available = parse_names(&query(&path, component.list_arg())?);
+            missing.extend(
+                names
+                    .into_iter()
+                    .filter(|name| !available.contains(*name))
+                    .map(|name| format!("{component} `{name}`")),
+            );
+        }
+        if !missing.is_empty() {
+            return Err(err_new!(
+                Kind::FfmpegUnavailable,
+                &format!(
+                    "{} (version {version}) is missing {}",
+                    path.display(),
+                    missing.join(", ")
+                )
+            ));
+        }
+        Ok(FfmpegInfo { path, version })
+    }
+
+    /// 生成 `format` 格式的视频需要的 `FFmpeg` 组件
+    fn required_components(&self, format: OutputFormat) -> Vec<(Component, &'static str)> {
+        let mut required = vec![
+            (Component::Filter, "color"),
+            (Component::Filter, "overlay"),
+            (Component::Encoder, self.encoder.codec.name()),
+        ];
+        required.push(match self.pipeline {
+            Pipeline::Segments => (Component::Demuxer, "concat"),
+            Pipeline::Stream => (Component::Demuxer, "rawvideo"),
+        });
+        if self.chapters && format.is_video() {
+            required.push((Component::Demuxer, "ffmetadata"));
+        }
+        if let (false, Some(codec)) = (self.audio.is_empty(), format.audio_codec()) {
+            required.extend(
+                ["amix", "atrim", "asetpts", "adelay", "volume", "afade"]
+                    .map(|name| (Component::Filter, name)),
+            );
+            required.push((Component::Encoder, codec));
+        }
+        match format {
+            OutputFormat::Mp4 => {}
+            OutputFormat::WebM if self.encoder.codec == VideoCodec::LibvpxVp9 => {}
+            OutputFormat::WebM => required.push((Component::Encoder, "libvpx-vp9")),
+            OutputFormat::Gif { .. } => {
+                required.extend(
+                    ["split", "palettegen", "paletteuse"].map(|name| (Component::Filter, name)),
+                );
+                required.push((Component::Encoder, "gif"));
+            }
+            OutputFormat::WebP { .. } => required.push((Component::Encoder, "libwebp")),
+            OutputFormat::Apng => required.push((Component::Encoder, "apng")),
+            OutputFormat::PngSequence => required.push((Component::Encoder, "png")),
+        }
+        required
+    }
+}
+
+/// 查找可执行文件：包含目录的路径直接检查文件是否存在，否则在 `PATH` 中查找
+fn find_program(program: &Path) -> Option<PathBuf> {
+    if program.components().count() > 1 {
+        return program.is_file().then(|| program.to_path_buf());
+    }
+    let name = if program.extension().is_none() {
+        program.with_extension(std::env::consts::EXE_EXTENSION)
+    } else {
+        program.to_path_buf()
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&name))
+        .find(|path| path.is_file())
+}
+
+/// 运行 `ffmpeg -hide_banner <arg>` 并返回标准输出
+fn query(path: &Path, arg: &str) -> Result<String> {
+    let output = Command::new(path)
+        .args(["-hide_banner", arg])
+        .output()
+        .map_err(|e| {
+            err_new!(
+                Kind::FfmpegUnavailable,
+                &format!("failed to run {}: {e}", path.display())
+            )
+        })?;
+    if !output.status.success() {
+        return Err(err_new!(
+            Kind::FfmpegUnavailable,
+            &format!("`{} {arg}` failed with {}", path.display(), output.status)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 从版本号中解析主版本号和次版本号，例如 `n6.1.1` 解析为 `(6, 1)`
+///
+/// 从源码构建的版本号形如 `N-113000-g1234abcd`，无法解析，返回 `None`。
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let version = version.strip_prefix('n').unwrap_or(version);
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts
+        .next()
+        .and_then(|minor| minor.parse().ok())
+        .unwrap_or(0);
+    Some((major, minor))
+}
+
+/// 解析 `-filters`、`-encoders`、`-demuxers` 的输出，返回所有组件名
+///
+/// 每行第一列是标志，第二列是名称，分离器可能用逗号列出多个名称。
+fn parse_names(list: &str) -> HashSet<String> {
+    list.lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .flat_map(|names| names.split(','))
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_version("6.1.1-3ubuntu5"), Some((6, 1)));
+        assert_eq!(parse_version("n7.0"), Some((7, 0)));
+        assert_eq!(parse_version("4.2.7-0ubuntu0.1"), Some((4, 2)));
+        assert_eq!(parse_version("5"), Some((5, 0)));
+        assert_eq!(parse_version("N-113000-g1234abcd"), None);
+
+        let filters = parse_names(
+            "Filters:\n  T.. = Timeline support\n  ---\n \
+             TSC overlay           VV->V      Overlay a video source on top of the input.\n \
+             ... color             |->V       Provide an uniformly colored input.\n",
+        );
+        assert!(filters.contains("overlay") && filters.contains("color"));
+        let demuxers = parse_names(
+            " D  concat          Virtual concatenation script\n D  mov,mp4,m4a   QuickTime / MOV\n",
+        );
+        assert!(demuxers.contains("concat") && demuxers.contains("mp4"));
+        assert!(!demuxers.contains("rawvideo"));
+    }
+}
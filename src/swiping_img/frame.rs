@@ -0,0 +1,110 @@
+use super::{config::parse_color, BigImg, Pipeline, Strip, Timeline};
+use crate::{
+    err_new, err_new_image,
+    error::{Kind, Result},
+    prelude::debug_print,
+};
+use image::{imageops, Rgba, RgbaImage};
+use std::path::Path;
+
+/// `FFmpeg` 颜色名中常用的部分，名称不区分大小写
+const COLOR_NAMES: [(&str, [u8; 3]); 12] = [
+    ("white", [255, 255, 255]),
+    ("black", [0, 0, 0]),
+    ("red", [255, 0, 0]),
+    ("green", [0, 128, 0]),
+    ("lime", [0, 255, 0]),
+    ("blue", [0, 0, 255]),
+    ("yellow", [255, 255, 0]),
+    ("cyan", [0, 255, 255]),
+    ("magenta", [255, 0, 255]),
+    ("gray", [128, 128, 128]),
+    ("silver", [192, 192, 192]),
+    ("orange", [255, 165, 0]),
+];
+
+impl BigImg<'_> {
+    /// 绘制最终视频在 `t` 秒时显示的画面，不编码视频。
+    ///
+    /// 画面为第 `floor(t * video_fps)` 帧，包括封面和结尾阶段。
+    /// `Segments` 方式的偏移量与 `overlay` 滤镜一样取整到像素，`Stream` 方式保留亚像素偏移。
+    ///
+    /// # Parameters
+    /// - `t`: 视频中的时间（秒）。
+    ///
+    /// # Errors
+    /// - 如果 `t` 不在 `0..视频时长` 范围内，则返回 `Err`。
+    /// - 如果视频背景颜色不是 `#RRGGBB`、`0xRRGGBB` 或常用的颜色名，则返回 `Err`。
+    /// - 如果绘制图像块时发生错误，则返回 `Err`。
+    ///
+    pub fn render_frame_at(&self, t: f64) -> Result<RgbaImage> {
+        let timeline = Timeline::new(self);
+        let duration = timeline.duration();
+        if !(0.0..duration).contains(&t) {
+            return Err(err_new!(
+                Kind::Other,
+                &format!("time {t}s is outside the video (0s..{duration:.3}s)")
+            ));
+        }
+        let frame = timeline.frame_of(t);
+        let offset = match self.pipeline {
+            Pipeline::Segments => f64::from(timeline.pixel_offset(frame)),
+            Pipeline::Stream => timeline.offset(frame),
+        };
+
+        let background = ffmpeg_color(&self.video_background_color).ok_or_else(|| {
+            err_new!(
+                Kind::Other,
+                &format!(
+                    "unsupported video background color `{}`",
+                    self.video_background_color
+                )
+            )
+        })?;
+        let mut image = RgbaImage::from_pixel(self.screen.0, self.screen.1, background);
+        imageops::overlay(&mut image, &Strip::new(self).frame(offset)?, 0, 0);
+        Ok(image)
+    }
+
+    /// 将最终视频在 `t` 秒时显示的画面保存为图像。
+    ///
+    /// # Parameters
+    /// - `t`: 视频中的时间（秒）。
+    /// - `save_name`: 图像文件名，相对于工作路径。
+    ///
+    /// # Errors
+    /// - 与 [`BigImg::render_frame_at`] 相同，保存图像失败时也返回 `Err`。
+    ///
+    pub fn save_frame_at<P: AsRef<Path>>(&self, t: f64, save_name: P) -> Result<()> {
+        self.render_frame_at(t)?
+            .save(self.work_dir.join(save_name.as_ref()))
+            .map_err(|e| err_new_image!(e))?;
+        debug_print(format!("{:?} successed", save_name.as_ref()));
+        Ok(())
+    }
+}
+
+/// 解析 `FFmpeg` 颜色，支持 `#RRGGBB[AA]`、`0xRRGGBB[AA]` 和常用的颜色名
+fn ffmpeg_color(color: &str) -> Option<Rgba<u8>> {
+    if let Some(hex) = color.strip_prefix("0x").or_else(|| color.strip_prefix('#')) {
+        return parse_color(hex).ok();
+    }
+    COLOR_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(color))
+        .map(|&(_, [r, g, b])| Rgba([r, g, b, 255]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffmpeg_color() {
+        assert_eq!(ffmpeg_color("White"), Some(Rgba([255, 255, 255, 255])));
+        assert_eq!(ffmpeg_color("#1796eb"), Some(Rgba([23, 150, 235, 255])));
+        assert_eq!(ffmpeg_color("0x1796eb80"), Some(Rgba([23, 150, 235, 128])));
+        assert_eq!(ffmpeg_color("AliceBlue"), None);
+        assert_eq!(ffmpeg_color("#123"), None);
+    }
+}
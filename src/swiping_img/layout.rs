@@ -0,0 +1,180 @@
+use super::{Axis, BigImg};
+use crate::{
+    err_new, err_new_image,
+    error::{Kind, Result},
+};
+use std::ops::Range;
+
+impl BigImg<'_> {
+    /// 计算每列图像块在长图中沿滑动方向的起始位置，最后一个元素为长图的总长度。
+    ///
+    /// 未设置 `fit_width` 时每列的长度都为 `width_chunk`；
+    /// 设置后每个图像块的长度随图片的宽高比变化，使图片恰好填满图片区域，并限制在 `fit_width` 范围内，
+    /// 每列的长度为其中最长的图像块的长度。
+    ///
+    /// # Errors
+    /// - 如果设置了 `fit_width` 且读取图片尺寸失败，则返回 `Err`。
+    ///
+    pub(super) fn layout(&self) -> Result<Vec<u32>> {
+        let rows = self.rows as usize;
+        let lens: Vec<u32> = match self.fit_width {
+            None => vec![self.width_chunk; self.columns()],
+            Some(bounds) => self
+                .chunks
+                .chunks(rows)
+                .map(|column| {
+                    column.iter().try_fold(bounds.0, |len, chunk| {
+                        let size = image::image_dimensions(chunk.pic_path())
+                            .map_err(|e| err_new_image!(e))?;
+                        Ok(len.max(fit_len(self.axis, size, self.pic_h, bounds)))
+                    })
+                })
+                .collect::<Result<_>>()?,
+        };
+        Ok(std::iter::once(0)
+            .chain(lens.iter().scan(0u32, |end, &len| {
+                *end = end.saturating_add(len);
+                Some(*end)
+            }))
+            .collect())
+    }
+
+    /// 第 `column` 列沿滑动方向的长度
+    pub(super) fn column_len(&self, column: usize) -> u32 {
+        self.column_offsets[column + 1] - self.column_offsets[column]
+    }
+
+    /// 第 `index` 个图像块沿滑动方向的长度，与所在列的长度相同
+    pub(super) fn chunk_len(&self, index: usize) -> u32 {
+        self.column_len(index / self.rows as usize)
+    }
+
+    /// 长图沿滑动方向的总长度
+    pub(super) fn strip_len(&self) -> u32 {
+        self.column_offsets.last().copied().unwrap_or(0)
+    }
+
+    /// 检查 `step` 列是否足够覆盖一屏，使相邻的分段可以首尾相接。
+    ///
+    /// # Errors
+    /// - 如果某个分段中除第一列以外的 `step - 1` 列短于一屏，则返回 `Err`。
+    ///
+    pub(super) fn check_step(&self) -> Result<()> {
+        let main = self.axis.split(self.screen).0;
+        if let Err(column) = divide_columns(&self.column_offsets, self.step as usize, main) {
+            let main_name = match self.axis {
+                Axis::Horizontal => "width_screen",
+                Axis::Vertical => "height_screen",
+            };
+            return Err(err_new!(
+                Kind::BigImgBuilderError,
+                &format!(
+                    "err: step is too small; columns {}..{} are shorter than {main_name} {main}, \
+                     step must be larger than the columns on screen",
+                    column + 1,
+                    column + self.step as usize
+                )
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 图片按宽高比填满图片区域时图像块沿滑动方向的长度，限制在 `bounds` 范围内。
+///
+/// # Parameters
+/// - `axis`: 滑动方向。
+/// - `size`: 图片的尺寸 `(宽度, 高度)`。
+/// - `pic_h`: 图片区域垂直于滑动方向的长度。
+/// - `bounds`: 长度的范围 `(最小值, 最大值)`。
+///
+fn fit_len(axis: Axis, size: (u32, u32), pic_h: u32, (min, max): (u32, u32)) -> u32 {
+    let (main, cross) = axis.split(size);
+    let len = (f64::from(pic_h) * f64::from(main) / f64::from(cross.max(1))).round();
+    (len as u32).clamp(min, max)
+}
+
+/// 按列划分分段长图，每个分段最多有 `step` 列。
+///
+/// 下一分段从能与本分段末尾一起覆盖一屏的最后一列开始，
+/// 因此滑动到下一分段之前，本分段的长图总是足够覆盖整个屏幕。列的长度相同时，相邻分段共享一屏的列数。
+///
+/// # Parameters
+/// - `offsets`: 每列的起始位置，最后一个元素为长图的总长度，见 [`BigImg::layout`]。
+/// - `step`: 每个分段的最大列数。
+/// - `main`: 沿滑动方向的屏幕长度。
+///
+/// # Errors
+/// - 如果某个分段无法前进到下一列，则返回该分段的第一列。
+///
+pub(super) fn divide_columns(
+    offsets: &[u32],
+    step: usize,
+    main: u32,
+) -> std::result::Result<Vec<Range<usize>>, usize> {
+    let columns = offsets.len().saturating_sub(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + step).min(columns);
+        ranges.push(start..end);
+        if end == columns {
+            return Ok(ranges);
+        }
+        // 起始位置加上一屏不超过本分段末尾的列数，最后一列即为下一分段的第一列
+        let fits = offsets.partition_point(|&o| o.saturating_add(main) <= offsets[end]);
+        if fits <= start + 1 {
+            return Err(start);
+        }
+        start = fits - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_len() {
+        let bounds = (240, 720);
+        assert_eq!(fit_len(Axis::Horizontal, (1600, 1000), 300, bounds), 480);
+        assert_eq!(fit_len(Axis::Horizontal, (4000, 1000), 300, bounds), 720);
+        assert_eq!(fit_len(Axis::Horizontal, (500, 1000), 300, bounds), 240);
+        // 纵向滑动时图片区域的宽度为 `pic_h`
+        assert_eq!(fit_len(Axis::Vertical, (1000, 1600), 300, bounds), 480);
+    }
+
+    #[test]
+    fn test_divide_columns() {
+        // 列的长度相同时与按 `step - overlap` 列划分一致
+        let offsets: Vec<u32> = (0..=12).map(|c| c * 240).collect();
+        assert_eq!(
+            divide_columns(&offsets, 6, 960),
+            Ok(vec![0..6, 2..8, 4..10, 6..12])
+        );
+        // 图像块不足一屏时只有一个分段
+        let ranges = divide_columns(&offsets[..4], 3, 960).unwrap();
+        assert_eq!((ranges.len(), &ranges[0]), (1, &(0..3)));
+        assert_eq!(
+            divide_columns(&offsets, 5, 960),
+            Ok(vec![0..5, 1..6, 2..7, 3..8, 4..9, 5..10, 6..11, 7..12])
+        );
+        assert_eq!(divide_columns(&offsets, 4, 960), Err(0));
+
+        // 每个分段在滑动到下一分段之前都能覆盖一屏
+        let lens = [240, 720, 300, 240, 480, 240, 260, 720, 240, 500];
+        let offsets: Vec<u32> = std::iter::once(0)
+            .chain(lens.iter().scan(0, |end, len| {
+                *end += len;
+                Some(*end)
+            }))
+            .collect();
+        let ranges = divide_columns(&offsets, 5, 960).unwrap();
+        assert_eq!(ranges.first().unwrap().start, 0);
+        assert_eq!(ranges.last().unwrap().end, lens.len());
+        for w in ranges.windows(2) {
+            assert!(w[1].start > w[0].start);
+            assert!(offsets[w[1].start] + 960 <= offsets[w[0].end]);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use super::{cache::Fnv64, BigImg};
+use crate::{
+    err_new, err_new_io,
+    error::{Kind, Result},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    ops::Range,
+    path::Path,
+};
+
+/// 清单文件名，位于工作路径中
+const MANIFEST: &str = "segments.json";
+
+/// 分段视频清单
+///
+/// 记录工作路径中每个分段视频由哪些图像块和参数生成，
+/// 重新生成时跳过图像块和参数都未改变的分段，直接复用已有的视频。
+/// 只在启用缓存时读取和写入，禁用缓存时分段视频在合并后删除，没有可以复用的视频。
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct Manifest {
+    segments: BTreeMap<String, Entry>,
+}
+
+/// 清单中的一个分段视频
+///
+/// # Parameters
+///
+/// * `chunks`: 生成该视频的图像块在 `chunks` 中的范围。
+/// * `key`: 图像块内容和生成参数的哈希值。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    chunks: Range<usize>,
+    key: String,
+}
+
+impl Manifest {
+    /// 读取工作路径中的清单，不存在或无法解析时返回空清单。
+    pub(super) fn load(work_dir: &Path) -> Self {
+        std::fs::read(work_dir.join(MANIFEST))
+            .ok()
+            .and_then(|buf| serde_json::from_slice(&buf).ok())
+            .unwrap_or_default()
+    }
+
+    /// 将清单写入工作路径，并删除旧清单中有而新清单中没有的分段视频。
+    ///
+    /// # Parameters
+    /// - `work_dir`: 工作路径。
+    /// - `old`: 本次生成前的清单。
+    ///
+    /// # Errors
+    /// - 如果清单写入失败，则返回 `Err`。
+    ///
+    pub(super) fn save(&self, work_dir: &Path, old: &Self) -> Result<()> {
+        for name in old.segments.keys() {
+            if !self.segments.contains_key(name) {
+                let _ = std::fs::remove_file(work_dir.join(name));
+            }
+        }
+        let buf =
+            serde_json::to_vec_pretty(self).map_err(|e| err_new!(Kind::Other, &e.to_string()))?;
+        std::fs::write(work_dir.join(MANIFEST), buf).map_err(|e| err_new_io!(e))
+    }
+
+    /// 分段视频是否存在且与清单记录一致
+    pub(super) fn is_fresh(
+        &self,
+        work_dir: &Path,
+        name: &Path,
+        chunks: &Range<usize>,
+        key: u64,
+    ) -> bool {
+        self.segments
+            .get(name.to_string_lossy().as_ref())
+            .is_some_and(|entry| entry.chunks == *chunks && entry.key == format!("{key:016x}"))
+            && work_dir.join(name).exists()
+    }
+
+    /// 记录分段视频
+    pub(super) fn insert(&mut self, name: &Path, chunks: Range<usize>, key: u64) {
+        self.segments.insert(
+            name.to_string_lossy().into_owned(),
+            Entry {
+                chunks,
+                key: format!("{key:016x}"),
+            },
+        );
+    }
+}
+
+impl BigImg<'_> {
+    /// 计算分段视频的键，由图像块内容、样式参数、视频参数和 `params` 决定。
+    ///
+    /// # Parameters
+    /// - `chunks`: 分段包含的图像块在 `chunks` 中的范围。
+    /// - `params`: 该分段特有的生成参数，例如帧数和 `overlay` 表达式。
+    ///
+    /// # Errors
+    /// - 如果图片文件读取失败，则返回 `Err`。
+    ///
+    pub(super) fn segment_key(&self, chunks: Range<usize>, params: &str) -> Result<u64> {
+        let mut hasher = Fnv64::default();
+        for index in chunks {
+            self.cache_key(&self.chunks[index], self.chunk_len(index))?
+                .hash(&mut hasher);
+        }
+        self.screen.hash(&mut hasher);
+        self.video_background_color.hash(&mut hasher);
+        self.video_fps.hash(&mut hasher);
+        self.encoder.args().hash(&mut hasher);
+        params.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swiping_img::{test_builder, test_chunks};
+
+    #[test]
+    fn test_round_trip() {
+        let work_dir =
+            std::env::temp_dir().join(format!("to_video_manifest_{}", std::process::id()));
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let (kept, dropped) = (Path::new("0.mp4"), Path::new("1.mp4"));
+        std::fs::write(work_dir.join(kept), b"").unwrap();
+        std::fs::write(work_dir.join(dropped), b"").unwrap();
+
+        let mut old = Manifest::default();
+        old.insert(kept, 0..6, 1);
+        old.insert(dropped, 4..10, 2);
+        old.save(&work_dir, &Manifest::default()).unwrap();
+
+        let loaded = Manifest::load(&work_dir);
+        assert!(loaded.is_fresh(&work_dir, kept, &(0..6), 1));
+        // 图像块或参数改变的分段需要重新生成
+        assert!(!loaded.is_fresh(&work_dir, kept, &(0..6), 3));
+        assert!(!loaded.is_fresh(&work_dir, kept, &(0..5), 1));
+        assert!(!loaded.is_fresh(&work_dir, Path::new("2.mp4"), &(0..6), 1));
+
+        // 新清单中没有的分段视频被删除，删除后不再复用
+        let mut manifest = Manifest::default();
+        manifest.insert(kept, 0..6, 1);
+        manifest.save(&work_dir, &loaded).unwrap();
+        assert!(!work_dir.join(dropped).exists());
+        assert!(!Manifest::load(&work_dir).is_fresh(&work_dir, dropped, &(4..10), 2));
+        std::fs::remove_file(work_dir.join(kept)).unwrap();
+        assert!(!Manifest::load(&work_dir).is_fresh(&work_dir, kept, &(0..6), 1));
+        std::fs::remove_dir_all(&work_dir).unwrap();
+    }
+
+    #[test]
+    fn test_segment_key() {
+        let old_chunks = test_chunks(&["a", "b"]);
+        let new_chunks = test_chunks(&["a", "c"]);
+        let old = test_builder(&old_chunks).build().unwrap();
+        let new = test_builder(&new_chunks).build().unwrap();
+        // 只有包含改变的图像块或参数改变的分段需要重新生成
+        assert_eq!(
+            old.segment_key(0..1, "").unwrap(),
+            new.segment_key(0..1, "").unwrap()
+        );
+        assert_ne!(
+            old.segment_key(0..2, "").unwrap(),
+            new.segment_key(0..2, "").unwrap()
+        );
+        assert_ne!(
+            old.segment_key(0..1, "").unwrap(),
+            old.segment_key(0..1, "t").unwrap()
+        );
+    }
+}
@@ -1,19 +1,148 @@
+mod audio;
+mod cache;
+mod cancel;
 pub mod chunk;
+pub mod config;
 mod draw;
+mod encoder;
+mod ffmpeg;
+mod frame;
+mod layout;
+mod manifest;
+mod motion;
+mod mux;
+mod output;
+mod plan;
+mod preview;
+mod progress;
+mod render;
+mod stream;
+mod strip;
+pub mod subtitle;
+mod timeline;
 
 use crate::{
-    err_new, err_new_image, err_new_io, err_new_tryfrom,
-    error::{Kind, Result},
+    err_new, err_new_ffmpeg, err_new_image, err_new_io,
+    error::{FfmpegError, Kind, Result},
     prelude::debug_print,
 };
 use ab_glyph::FontVec;
-use image::{DynamicImage, GenericImage, GenericImageView, Rgba};
+use image::{DynamicImage, GenericImage, Rgba};
+use manifest::Manifest;
+use serde::{Deserialize, Serialize};
 use std::{
+    ffi::{OsStr, OsString},
     fmt::{self, Debug},
+    io::{BufRead, BufReader, Read},
+    ops::Range,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
 };
-pub use {chunk::Chunk, draw::Draw};
+pub use {
+    audio::AudioTrack,
+    cancel::CancelToken,
+    chunk::Chunk,
+    config::BigImgConfig,
+    draw::Draw,
+    encoder::{EncoderSettings, VideoCodec},
+    ffmpeg::{FfmpegInfo, FFMPEG_ENV},
+    motion::{Direction, Motion, MotionProfile, Scroll, SwipSpeed},
+    output::OutputFormat,
+    plan::{Plan, SegmentPart, SegmentVideo},
+    preview::Preview,
+    progress::Progress,
+    strip::Strip,
+    subtitle::Cue,
+    timeline::{Segment, Timeline},
+};
+
+/// 视频生成方式
+///
+/// * `Segments`: 将长图分段保存为图片，由 `FFmpeg` 的 `overlay` 滤镜滑动生成分段视频后合并。
+/// * `Stream`: 逐帧计算画面，以 `rawvideo` 格式传给单个 `FFmpeg` 进程，不生成中间文件。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pipeline {
+    #[default]
+    Segments,
+    Stream,
+}
+
+impl FromStr for Pipeline {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "segments" => Ok(Self::Segments),
+            "stream" => Ok(Self::Stream),
+            _ => Err(format!("expected `segments` or `stream`, got `{s}`")),
+        }
+    }
+}
+
+/// 滑动方向
+///
+/// * `Horizontal`: 图像块从左到右排列，画面从右向左滑动。图像块宽为 `width_chunk`、高为屏幕高度，
+///   图片和上下文本从上到下排列。
+/// * `Vertical`: 图像块从上到下排列，画面向上滑动。图像块宽为屏幕宽度、高为 `width_chunk`，
+///   图片和上下文本从左到右排列，`pic_h` 和 `text_up_h` 为它们的宽度。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    /// 将沿滑动方向的长度 `main` 和垂直于滑动方向的长度 `cross` 组合为 `(宽度, 高度)`。
+    ///
+    /// `cross` 为 0 时得到长图中 `main` 处的坐标 `(x, y)`。
+    #[must_use]
+    pub fn size(self, main: u32, cross: u32) -> (u32, u32) {
+        match self {
+            Self::Horizontal => (main, cross),
+            Self::Vertical => (cross, main),
+        }
+    }
+
+    /// 将 `(宽度, 高度)` 拆分为沿滑动方向和垂直于滑动方向的长度 `(main, cross)`
+    #[must_use]
+    pub fn split(self, (width, height): (u32, u32)) -> (u32, u32) {
+        self.size(width, height)
+    }
+
+    /// `overlay` 滤镜中沿滑动方向的坐标参数名
+    fn overlay_param(self) -> &'static str {
+        match self {
+            Self::Horizontal => "x",
+            Self::Vertical => "y",
+        }
+    }
+}
+
+impl FromStr for Axis {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "horizontal" => Ok(Self::Horizontal),
+            "vertical" => Ok(Self::Vertical),
+            _ => Err(format!("expected `horizontal` or `vertical`, got `{s}`")),
+        }
+    }
+}
+
+/// 每个 `FFmpeg` 命令前加上的参数，通过 stdout 输出编码进度
+const PROGRESS_ARGS: [&str; 3] = ["-progress", "pipe:1", "-nostats"];
+
+/// 等待 `FFmpeg` 进程时检查取消令牌的间隔
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// 接收进度事件的回调
+type ProgressFn<'a> = Box<dyn Fn(&Progress) + Send + Sync + 'a>;
 
 /// 大图像处理结构体
 ///
@@ -25,9 +154,12 @@ pub use {chunk::Chunk, draw::Draw};
 /// * `work_dir`: 图像操作的工作路径。
 /// * `chunks`: 图像块数据数组的引用。
 /// * `screen`: 显示图像的屏幕分辨率（宽度，高度）。
-/// * `step`: 每次处理图像块的数量。
-/// * `width_chunk`: 每个图像块的宽度。
-/// * `overlap`: 重叠图像块数，即屏幕能同时显示图像块的数量。
+/// * `step`: 每次处理图像块的列数，每列有 `rows` 个图像块。
+/// * `width_chunk`: 每个图像块沿滑动方向的长度，横向滑动时为宽度，纵向滑动时为高度。
+/// * `fit_width`: 图像块沿滑动方向的长度范围 `(最小值, 最大值)`，设置后长度随图片的宽高比变化，不再使用 `width_chunk`。
+/// * `column_offsets`: 每列图像块在长图中沿滑动方向的起始位置，最后一个元素为长图的总长度。
+/// * `axis`: 滑动方向。
+/// * `rows`: 每列图像块的数量，同一列的图像块沿垂直于滑动方向排列，一列为滑动的单位。
 /// * `text_background_color`: 文本的背景颜色，包括上下两种颜色。
 /// * `text_color`: 文本的颜色。
 /// * `max_scale`: 字体的最大缩放因子。
@@ -35,18 +167,36 @@ pub use {chunk::Chunk, draw::Draw};
 /// * `text_up_h`: 图像块中的上方文本的高度。
 /// * `text_down_h`: 图像块中的下方文本的高度。
 /// * `font`: 文本渲染使用的字体。
+/// * `layout_scale`: 图像块中边距和圆角等固定尺寸的缩放比例，预览时小于 `1`。
 /// * `video_cover_time`: 视频封面图像的持续时间。
 /// * `video_ending_time`: 视频结束图像的持续时间。
 /// * `video_background_color`: 视频的背景颜色，以字符串表示。
-/// * `video_swip_speed`: 视频的滑动速度，用视频滑动 `width_chunk` 所需的秒数表示。
+/// * `video_swip_speed`: 视频的滑动速度，用视频滑动一个图像块所需的秒数或每秒滑动的像素数表示。
 /// * `video_fps`: 视频的帧率（每秒帧数）。
+/// * `pipeline`: 视频生成方式。
+/// * `motion`: 滑动阶段的运动曲线。
+/// * `direction`: 滑动方向，决定封面和结尾画面取自长图的哪一端。
+/// * `audio`: 视频的音频轨道。
+/// * `subtitles`: 是否生成与图像块同步的 `SRT` 和 `WebVTT` 字幕文件。
+/// * `chapters`: 是否在视频中写入与图像块同步的章节。
+/// * `output_format`: 输出格式，未设置时根据输出文件的扩展名推断。
+/// * `encoder`: 视频编码参数，所有分段使用相同的参数编码。
+/// * `workers`: 并行绘制图像块的线程数。
+/// * `cache`: 是否在工作路径的 `cache` 目录中缓存绘制好的图像块，并保留分段视频以便下次复用。
+/// * `ffmpeg`: `FFmpeg` 可执行文件的路径，只有文件名时在 `PATH` 中查找。
+/// * `progress`: 接收进度事件的回调。
+/// * `cancel`: 取消令牌，取消后在下一个检查点停止生成并清理未完成的文件。
+/// * `style_hash`: 影响图像块绘制结果的样式参数的哈希值，用于计算缓存键。
 pub struct BigImg<'a> {
     work_dir: PathBuf,
     chunks: &'a [Chunk],
     screen: (u32, u32),
     step: u32,
     width_chunk: u32,
-    overlap: u32,
+    fit_width: Option<(u32, u32)>,
+    column_offsets: Vec<u32>,
+    axis: Axis,
+    rows: u32,
     text_background_color: (Rgba<u8>, Rgba<u8>),
     text_color: Rgba<u8>,
     max_scale: f32,
@@ -54,11 +204,26 @@ pub struct BigImg<'a> {
     text_up_h: u32,
     text_down_h: u32,
     font: FontVec,
+    layout_scale: f32,
     video_cover_time: u32,
     video_ending_time: u32,
     video_background_color: String,
-    video_swip_speed: u32,
+    video_swip_speed: SwipSpeed,
     video_fps: u32,
+    pipeline: Pipeline,
+    motion: Box<dyn Motion>,
+    direction: Direction,
+    audio: Vec<AudioTrack>,
+    subtitles: bool,
+    chapters: bool,
+    output_format: Option<OutputFormat>,
+    encoder: EncoderSettings,
+    workers: usize,
+    cache: bool,
+    ffmpeg: PathBuf,
+    progress: Option<ProgressFn<'a>>,
+    cancel: CancelToken,
+    style_hash: u64,
 }
 
 impl<'a> BigImg<'a> {
@@ -99,217 +264,552 @@ impl<'a> BigImg<'a> {
 impl BigImg<'_> {
     /// 组合所有图像块并生成最终视频。
     ///
+    /// 输出格式未设置时根据 `save_name` 的扩展名推断。
+    /// 非 `MP4` 格式先生成 `MP4` 中间视频，再转换为输出格式。
+    ///
     /// # Parameters
     /// - `save_name`: 最终视频文件名。
     ///
     /// # Errors
+    /// - 如果设置了音频或章节而输出格式不是视频，则在生成任何文件之前返回 `Err`。
+    /// - 如果 `FFmpeg` 预检查失败，则在生成任何文件之前返回 `Err`，见 [`BigImg::preflight`]。
     /// - 如果图像处理或保存过程中发生错误，则返回 `Err`。
     /// - 如果 `FFmpeg` 命令执行失败，则返回 `Err`。
+    /// - 如果通过取消令牌取消，则删除未完成的文件后返回 `Cancelled` 类型错误。
     ///
     pub fn run<P: AsRef<Path>>(&self, save_name: P) -> Result<()> {
-        let chunks = self.divide();
-        let mut results = Vec::with_capacity(chunks.len() + 2);
-
-        for (index, &chunk) in chunks.iter().enumerate() {
-            let target = self.combain_chunk(chunk)?;
-            if index == 0 {
-                let cover = target.crop_imm(0, 0, self.screen.0, self.screen.1);
-                let cover_pic_name = Path::new("cover.png");
-                // 保存组合后的图像
-                cover
-                    .save(self.work_dir.join(cover_pic_name))
-                    .map_err(|e| err_new_image!(e))?;
-                debug_print(format!("{cover_pic_name:?} successed"));
-
-                let cover_video_name = cover_pic_name.with_extension("mp4");
-                self.generate_endpoint_video(
-                    cover_pic_name,
-                    &cover_video_name,
-                    self.video_cover_time,
-                )?;
-                results.push(cover_video_name);
+        let save_name = save_name.as_ref();
+        let format = self.checked_format(save_name)?;
+        self.preflight(save_name)?;
+        let timeline = Timeline::new(self);
+
+        let video_name = self.video_name(save_name, format);
+        let need_mux = video_name != save_name;
+        match self.pipeline {
+            Pipeline::Segments => self.run_segments(&timeline, &video_name)?,
+            Pipeline::Stream => self.run_stream(&timeline, &video_name)?,
+        }
+
+        let cues = if self.subtitles || self.chapters {
+            self.cues(&timeline)
+        } else {
+            Vec::new()
+        };
+        if self.subtitles {
+            for (extension, content) in [
+                ("srt", subtitle::to_srt(&cues)),
+                ("vtt", subtitle::to_vtt(&cues)),
+            ] {
+                let subtitle_name = save_name.with_extension(extension);
+                std::fs::write(self.work_dir.join(&subtitle_name), content)
+                    .map_err(|e| err_new_io!(e))?;
+                println!("{} successed", subtitle_name.display());
             }
+        }
+        if need_mux {
+            self.emit(Progress::Mux);
+            let muxed = self.check_cancelled().and_then(|()| {
+                self.mux(
+                    &timeline,
+                    &video_name,
+                    save_name,
+                    format,
+                    self.chapters.then_some(cues.as_slice()),
+                )
+            });
+            let _ = std::fs::remove_file(self.work_dir.join(&video_name));
+            muxed?;
+            println!("{} successed", format.output_name(save_name).display());
+        }
+        self.emit(Progress::Finished);
+        Ok(())
+    }
 
-            // 保存组合后的图像
-            let mid_pic_name = format!("{index:0>2}.png");
-            let mid_pic_name = Path::new(&mid_pic_name);
-            target
-                .save(self.work_dir.join(mid_pic_name))
-                .map_err(|e| err_new_image!(e))?;
-            debug_print(format!("{mid_pic_name:?} successed"));
-
-            let mid_video_name = mid_pic_name.with_extension("mp4");
-            self.generate_mid_video(chunk.len() as u32, mid_pic_name, &mid_video_name)?;
-            results.push(mid_video_name);
-
-            if index == chunks.len() - 1 {
-                let w = target.dimensions().0;
-                let ending = target.crop_imm(w - self.screen.0, 0, self.screen.0, self.screen.1);
-                let ending_pic_name = Path::new("ending.png");
-                // 保存组合后的图像
-                ending
-                    .save(self.work_dir.join(ending_pic_name))
-                    .map_err(|e| err_new_image!(e))?;
-                debug_print(format!("{ending_pic_name:?} successed"));
-
-                let ending_video_name = ending_pic_name.with_extension("mp4");
-                self.generate_endpoint_video(
-                    ending_pic_name,
-                    &ending_video_name,
-                    self.video_ending_time,
+    /// 合并分段或逐帧编码生成的视频文件名。
+    ///
+    /// 有音频轨道、章节或需要转换格式时先生成只有视频流的中间视频，再转换格式并混入音频和章节，
+    /// 否则直接生成 `save_name`。
+    fn video_name(&self, save_name: &Path, format: OutputFormat) -> PathBuf {
+        let chapters = self.chapters && format.is_video();
+        if format != OutputFormat::Mp4 || !self.audio.is_empty() || chapters {
+            save_name.with_extension("video.mp4")
+        } else {
+            save_name.to_path_buf()
+        }
+    }
+
+    /// 输出格式，未设置时根据 `save_name` 的扩展名推断，无法推断时为 `MP4`
+    fn resolve_format(&self, save_name: &Path) -> OutputFormat {
+        self.output_format
+            .or_else(|| OutputFormat::from_path(save_name))
+            .unwrap_or_default()
+    }
+
+    /// 同 [`BigImg::resolve_format`]，并检查推断出的格式能否包含音频和章节。
+    ///
+    /// # Errors
+    /// - 如果设置了音频或章节而输出格式不是视频，则返回 `Err`。
+    ///
+    fn checked_format(&self, save_name: &Path) -> Result<OutputFormat> {
+        let format = self.resolve_format(save_name);
+        format
+            .check_streams(!self.audio.is_empty(), self.chapters)
+            .map_err(|e| err_new!(Kind::Other, &format!("{}: {e}", save_name.display())))?;
+        Ok(format)
+    }
+
+    /// 分段生成视频后合并。
+    ///
+    /// 启用缓存时在工作路径中保留分段视频并记录到清单中，
+    /// 重新生成时只生成图像块或参数改变了的分段，其余分段直接复用。
+    ///
+    /// # Parameters
+    /// - `timeline`: 视频时间轴。
+    /// - `save_name`: 合并后的视频文件名。
+    ///
+    /// # Errors
+    /// - 如果图像处理或保存过程中发生错误，则返回 `Err`。
+    /// - 如果 `FFmpeg` 命令执行失败，则返回 `Err`。
+    ///
+    /// 生成或合并分段视频失败或取消时，删除分段长图，未启用缓存时同时删除分段视频。
+    ///
+    fn run_segments(&self, timeline: &Timeline, save_name: &Path) -> Result<()> {
+        let segments = timeline.segments(&self.divide(), &self.column_offsets, self.rows);
+        let mut results = Vec::new();
+        let old = if self.cache {
+            Manifest::load(&self.work_dir)
+        } else {
+            Manifest::default()
+        };
+        let mut manifest = Manifest::default();
+        let videos = self.segment_videos(timeline, &segments);
+        let generated = self.generate_segments(
+            timeline,
+            &segments,
+            &videos,
+            &old,
+            &mut manifest,
+            &mut results,
+        );
+        if let Err(e) = generated {
+            self.remove_partial_segments(&videos, &results);
+            return Err(e);
+        }
+        // 分段视频都已生成，先保存清单，合并失败或取消后重新生成时仍可复用
+        if self.cache {
+            manifest.save(&self.work_dir, &old)?;
+        }
+
+        let combained = self.combain(&mut results, save_name, timeline.duration());
+        if let Err(e) = combained {
+            self.remove_partial_segments(&videos, &results);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// 依次生成分段视频，启用缓存时未改变的分段直接复用。
+    ///
+    /// # Parameters
+    /// - `timeline`: 视频时间轴。
+    /// - `segments`: 时间轴上的分段。
+    /// - `videos`: 需要生成的分段视频，见 [`BigImg::segment_videos`]。
+    /// - `old`: 本次生成前的清单。
+    /// - `manifest`: 记录本次生成的分段视频，禁用缓存时保持为空。
+    /// - `results`: 按顺序保存生成或复用的分段视频文件名。
+    ///
+    /// # Errors
+    /// - 如果已经取消，则返回 `Cancelled` 类型错误。
+    /// - 如果图像处理、保存或 `FFmpeg` 命令执行失败，则返回 `Err`。
+    ///
+    fn generate_segments(
+        &self,
+        timeline: &Timeline,
+        segments: &[Segment],
+        videos: &[SegmentVideo],
+        old: &Manifest,
+        manifest: &mut Manifest,
+        results: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        // 分段长图只在有分段需要重新生成时绘制，同一分段的视频共用
+        let mut target = None;
+        let mut target_segment = None;
+
+        for (index, video) in videos.iter().enumerate() {
+            self.check_cancelled()?;
+            let segment = &segments[video.segment];
+            if target_segment != Some(video.segment) {
+                target = None;
+                target_segment = Some(video.segment);
+            }
+
+            // 禁用缓存时不复用分段视频，不需要读取图片计算键
+            let key = if self.cache {
+                let params = self.segment_params(timeline, segment, video);
+                Some(self.segment_key(segment.chunks.clone(), &params)?)
+            } else {
+                None
+            };
+            let reused = key
+                .is_some_and(|key| old.is_fresh(&self.work_dir, &video.name, &segment.chunks, key));
+            if !reused {
+                let target = self.segment_image(&mut target, segment.chunks.clone())?;
+                let pic_name = video.name.with_extension("png");
+                // 保存组合后的图像，封面和结尾只保存一屏
+                let saved = match video.part {
+                    SegmentPart::Scroll => target.save(self.work_dir.join(&pic_name)),
+                    SegmentPart::Cover | SegmentPart::Ending => {
+                        let endpoint = self.endpoint_x(timeline, segment, video);
+                        let (x, y) = self.axis.size(endpoint, 0);
+                        target
+                            .crop_imm(x, y, self.screen.0, self.screen.1)
+                            .save(self.work_dir.join(&pic_name))
+                    }
+                };
+                saved.map_err(|e| err_new_image!(e))?;
+                debug_print(format!("{pic_name:?} successed"));
+
+                let frames = video.frames.end - video.frames.start;
+                self.ffmpeg(
+                    &self.segment_args(timeline, segment, video),
+                    frames as f64 / f64::from(self.video_fps),
                 )?;
-                results.push(ending_video_name);
+                debug_print(format!("{:?} successed", video.name));
+            }
+            if let Some(key) = key {
+                manifest.insert(&video.name, segment.chunks.clone(), key);
+            }
+            results.push(video.name.clone());
+            self.emit(Progress::SegmentDone {
+                target: video.name.clone(),
+                index,
+                total: videos.len(),
+                reused,
+            });
+        }
+        Ok(())
+    }
+
+    /// 生成或合并分段视频失败或取消时删除已保存的分段长图，未启用缓存时同时删除已生成的分段视频。
+    ///
+    /// # Parameters
+    /// - `videos`: 需要生成的分段视频。
+    /// - `results`: 已生成或复用的分段视频文件名。
+    ///
+    fn remove_partial_segments(&self, videos: &[SegmentVideo], results: &[PathBuf]) {
+        for video in videos {
+            let _ = std::fs::remove_file(self.work_dir.join(video.name.with_extension("png")));
+        }
+        if !self.cache {
+            for result in results {
+                let _ = std::fs::remove_file(self.work_dir.join(result));
             }
         }
+    }
+
+    /// 获取分段长图，第一次调用时绘制。
+    ///
+    /// # Parameters
+    /// - `target`: 已绘制的分段长图，为 `None` 时绘制后保存在其中。
+    /// - `chunks`: 分段包含的图像块在 `chunks` 中的范围。
+    ///
+    /// # Errors
+    /// - 如果图像处理过程中发生错误，则返回 `Err`。
+    ///
+    fn segment_image<'t>(
+        &self,
+        target: &'t mut Option<DynamicImage>,
+        chunks: Range<usize>,
+    ) -> Result<&'t DynamicImage> {
+        if target.is_none() {
+            *target = Some(self.combain_chunk(chunks)?);
+        }
+        Ok(target.as_ref().expect("target is rendered"))
+    }
 
-        self.combain(&mut results, save_name.as_ref())?;
+    /// 只生成视频封面图像，不调用 `FFmpeg`。
+    ///
+    /// # Parameters
+    /// - `save_name`: 封面图像文件名，相对于工作路径。
+    ///
+    /// # Errors
+    /// - 如果图像处理或保存过程中发生错误，则返回 `Err`。
+    ///
+    pub fn save_cover<P: AsRef<Path>>(&self, save_name: P) -> Result<()> {
+        // 起始位置在屏幕内的列
+        let main = self.axis.split(self.screen).0;
+        let columns = self.column_offsets.partition_point(|&o| o < main);
+        let cover = self
+            .combain_chunk(self.column_chunks(0..columns.min(self.columns())))?
+            .crop_imm(0, 0, self.screen.0, self.screen.1);
+        cover
+            .save(self.work_dir.join(save_name.as_ref()))
+            .map_err(|e| err_new_image!(e))?;
+        debug_print(format!("{:?} successed", save_name.as_ref()));
         Ok(())
     }
 
     /// 将图像块分割成多个子块。
     ///
-    /// # Results
-    /// 返回一个包含分割后子块的向量。
+    /// 按整列划分，每个子块最多有 `step` 列，相邻子块共享足够覆盖一屏的列，见 [`layout::divide_columns`]；
+    /// 图像块不足一屏时只有一个子块。
     ///
-    fn divide(&self) -> Vec<&[Chunk]> {
-        let len = self.chunks.len();
-        (0..len - self.overlap as usize)
-            .step_by((self.step - self.overlap) as usize)
-            .map(|i| &self.chunks[i..(i + self.step as usize).min(len)])
+    /// # Results
+    /// 返回一个包含分割后子块在 `chunks` 中范围的向量。
+    ///
+    fn divide(&self) -> Vec<Range<usize>> {
+        let main = self.axis.split(self.screen).0;
+        layout::divide_columns(&self.column_offsets, self.step as usize, main)
+            .expect("step is checked in build")
+            .into_iter()
+            .map(|columns| self.column_chunks(columns))
             .collect()
     }
 
+    /// 图像块的列数
+    fn columns(&self) -> usize {
+        self.chunks.len().div_ceil(self.rows as usize)
+    }
+
+    /// 第 `columns` 列包含的图像块在 `chunks` 中的范围
+    fn column_chunks(&self, columns: Range<usize>) -> Range<usize> {
+        let rows = self.rows as usize;
+        let len = self.chunks.len();
+        (columns.start * rows).min(len)..(columns.end * rows).min(len)
+    }
+
+    /// 第 `index` 个图像块在整张长图中的位置 `(x, y)`
+    fn chunk_position(&self, index: usize) -> (u32, u32) {
+        let rows = self.rows as usize;
+        let (column, row) = (index / rows, (index % rows) as u32);
+        self.axis
+            .size(self.column_offsets[column], row * self.chunk_cross())
+    }
+
     /// 将多个图像块组合成一个完整的图像。
     ///
     /// # Parameters
-    /// - `chunk`: 要组合的图像块切片。
+    /// - `chunks`: 要组合的图像块在 `chunks` 中的范围，需要从一列的开头开始。
     ///
     /// # Results
     /// 如果成功，则返回组合后的 `DynamicImage`；如果失败，则返回 `Err`。
     ///
     /// # Errors
-    /// - 如果 `chunk` 为空，则返回 `Err`。
+    /// - 如果 `chunks` 为空，则返回 `Err`。
     /// - 如果图像处理过程中发生错误，则返回 `Err`。
     ///
-    fn combain_chunk(&self, chunk: &[Chunk]) -> Result<DynamicImage> {
-        if chunk.is_empty() {
+    fn combain_chunk(&self, chunks: Range<usize>) -> Result<DynamicImage> {
+        if chunks.is_empty() {
             return Err(err_new!(Kind::Other, "Empty chunk"));
         }
 
-        let len = u32::try_from(chunk.len()).map_err(|e| err_new_tryfrom!(e))?;
-        let mut target = DynamicImage::new_rgba8(len * self.width_chunk, self.screen.1);
+        let rows = self.rows as usize;
+        let (first, end) = (chunks.start / rows, chunks.end.div_ceil(rows));
+        let origin = self.column_offsets[first];
+        let (w, h) = self
+            .axis
+            .size(self.column_offsets[end] - origin, self.cross_len());
+        let mut target = DynamicImage::new_rgba8(w, h);
 
-        // 将每张图片绘制到目标图像中
-        for (i, item) in chunk.iter().enumerate() {
-            let img = item.draw_data(self).map_err(|e| err_new_image!(e))?;
+        // 并行绘制图像块后按顺序逐列复制到目标图像中，位置相对于第一列
+        let (origin_x, origin_y) = self.axis.size(origin, 0);
+        let images = self.render_chunks(chunks.clone())?;
+        for (index, img) in chunks.zip(&images) {
+            let (x, y) = self.chunk_position(index);
             target
-                .copy_from(&img, u32::try_from(i)? * self.width_chunk, 0)
+                .copy_from(img, x - origin_x, y - origin_y)
                 .map_err(|e| err_new_image!(e))?;
         }
         Ok(target)
     }
 
-    /// 生成视频封面或结尾视频。
+    /// 垂直于滑动方向的屏幕长度，即图像块中图片和文本排列方向的长度
+    fn cross_len(&self) -> u32 {
+        self.axis.split(self.screen).1
+    }
+
+    /// 图像块垂直于滑动方向的长度，每列的 `rows` 个图像块平分垂直于滑动方向的屏幕长度
+    fn chunk_cross(&self) -> u32 {
+        self.cross_len() / self.rows
+    }
+
+    /// 封面或结尾画面在分段长图中沿滑动方向的位置，滑动阶段的分段为 `0`。
+    ///
+    /// 封面和结尾分别取时间轴开始和结束时的偏移量，由滑动方向决定。
+    fn endpoint_x(&self, timeline: &Timeline, segment: &Segment, video: &SegmentVideo) -> u32 {
+        match video.part {
+            SegmentPart::Cover => timeline.start_offset().saturating_sub(segment.origin),
+            SegmentPart::Ending => timeline.end_offset().saturating_sub(segment.origin),
+            SegmentPart::Scroll => 0,
+        }
+    }
+
+    /// 分段视频特有的生成参数，用于计算分段视频的键。
+    fn segment_params(
+        &self,
+        timeline: &Timeline,
+        segment: &Segment,
+        video: &SegmentVideo,
+    ) -> String {
+        match video.part {
+            SegmentPart::Cover => format!(
+                "cover {} {}",
+                self.endpoint_x(timeline, segment, video),
+                timeline.cover_frames
+            ),
+            SegmentPart::Scroll => {
+                format!("mid {:?} {}", segment.frames, timeline.overlay_x(segment))
+            }
+            SegmentPart::Ending => format!(
+                "ending {} {}",
+                self.endpoint_x(timeline, segment, video),
+                timeline.ending_frames
+            ),
+        }
+    }
+
+    /// 生成分段视频的 `FFmpeg` 参数。
     ///
-    /// # Parameters
-    /// - `pic_name`: 素材图片名称。
-    /// - `video_name`: 生成视频名称。
-    /// - `video_time`: 视频时长（秒）。
+    /// 封面和结尾的素材图片只有一屏，画面保持不动；
+    /// 滑动阶段的视频帧数与分段在时间轴上的帧范围一致，每一帧的偏移量都由时间轴计算，
+    /// 保证相邻分段首尾衔接。
     ///
-    /// # Errors
-    /// - 如果 `FFmpeg` 命令执行失败，则返回 `Err`。
+    /// # Parameters
+    /// - `timeline`: 视频时间轴。
+    /// - `segment`: 素材图片对应的分段。
+    /// - `video`: 要生成的分段视频，素材图片为同名的 `png` 文件。
     ///
-    fn generate_endpoint_video(
+    fn segment_args(
         &self,
-        pic_name: &Path,
-        video_name: &Path,
-        video_time: u32,
-    ) -> Result<()> {
-        self.ffmpeg(&[
-            "-r",
-            "1",
-            "-loop",
-            "1",
-            "-i",
-            pic_name.to_str().unwrap(),
-            "-filter_complex",
-            &format!(
-                "color={}:s={}x{}:r={}[bg];[bg][0]overlay=shortest=1",
-                self.video_background_color, self.screen.0, self.screen.1, self.video_fps
-            ),
-            "-preset",
-            "fast",
-            "-t",
-            &video_time.to_string(),
-            "-y",
-            video_name.to_str().unwrap(),
-        ])?;
-        debug_print(format!("{video_name:?} successed"));
-        Ok(())
+        timeline: &Timeline,
+        segment: &Segment,
+        video: &SegmentVideo,
+    ) -> Vec<OsString> {
+        let mut filter = format!(
+            "color={}:s={}x{}:r={}[bg];[bg][0]overlay",
+            self.video_background_color, self.screen.0, self.screen.1, self.video_fps
+        );
+        if video.part == SegmentPart::Scroll {
+            filter.push_str(&format!(
+                "={}='{}'",
+                self.axis.overlay_param(),
+                timeline.overlay_x(segment)
+            ));
+        }
+        self.loop_image_args(
+            &video.name.with_extension("png"),
+            &filter,
+            video.frames.end - video.frames.start,
+            &video.name,
+        )
     }
 
-    /// 生成中间部分的视频。
+    /// 将图片循环作为输入，经过 `filter` 滤镜后按编码参数生成 `frames` 帧的视频的 `FFmpeg` 参数。
     ///
     /// # Parameters
-    /// - `len`: 素材图片中 `chunk` 数量。
     /// - `pic_name`: 素材图片名称。
+    /// - `filter`: `filter_complex` 滤镜，图片为第 0 个输入。
+    /// - `frames`: 视频帧数。
     /// - `video_name`: 生成视频名称。
     ///
-    /// # Errors
-    /// - 如果 `FFmpeg` 命令执行失败，则返回 `Err`。
-    ///
-    fn generate_mid_video(&self, len: u32, pic_name: &Path, video_name: &Path) -> Result<()> {
-        let adjust_len = len - self.overlap;
-        let run_seconds = self.video_swip_speed * adjust_len + 1;
-        let speed = self.width_chunk / self.video_swip_speed;
-
-        self.ffmpeg(&[
-            "-r",
-            "1",
-            "-loop",
-            "1",
-            "-t",
-            &run_seconds.to_string(),
-            "-i",
-            pic_name.to_str().unwrap(),
-            "-filter_complex",
-            &format!(
-                "color={}:s={}x{}:r={}[bg];[bg][0]overlay=x=-t*{speed}:shortest=1",
-                self.video_background_color, self.screen.0, self.screen.1, self.video_fps
-            ),
-            "-preset",
-            "fast",
-            "-y",
-            video_name.to_str().unwrap(),
-        ])?;
-        debug_print(format!("{video_name:?} successed"));
-        Ok(())
+    fn loop_image_args(
+        &self,
+        pic_name: &Path,
+        filter: &str,
+        frames: u64,
+        video_name: &Path,
+    ) -> Vec<OsString> {
+        let mut args: Vec<OsString> = ["-r", "1", "-loop", "1", "-i"].map(OsString::from).into();
+        args.push(ffmpeg_path(pic_name));
+        args.extend(["-filter_complex", filter].map(OsString::from));
+        args.extend(self.encoder.args().into_iter().map(OsString::from));
+        args.extend(["-frames:v".into(), frames.to_string().into(), "-y".into()]);
+        args.push(ffmpeg_path(video_name));
+        args
     }
 
-    #[allow(unused)]
     /// 执行带有指定参数的FFmpeg命令
     ///
+    /// 通过 `-progress` 读取编码进度，按 `duration` 计算百分比后发送 `Progress::Encoding` 事件。
+    /// 等待进程结束时定期检查取消令牌，取消后终止进程。
+    /// 取消或失败时删除未完成的输出文件。
+    ///
     /// # Parameters
     /// - `&self` - 包含工作路径配置的结构体实例引用
-    /// - `args` - 传递给ffmpeg命令行工具的字符串参数切片
+    /// - `args` - 传递给ffmpeg命令行工具的参数切片，路径参数可以不是 UTF-8，最后一个参数为输出文件
+    /// - `duration` - 输出文件的时长（秒），用于计算进度百分比
     ///
     /// # Results
     /// - 成功时返回Ok(())，失败时返回包含上下文信息的Err
     ///
     /// # Errors
     /// - 无法执行ffmpeg命令时返回IO错误
-    /// - ffmpeg进程返回非零状态码时打印stderr到控制台并返回Other类型错误
-    ///
-    fn ffmpeg(&self, args: &[&str]) -> Result<()> {
-        let command = Command::new("ffmpeg")
+    /// - ffmpeg进程返回非零状态码时返回 `Ffmpeg` 类型错误，包含参数、退出码和stderr的最后若干行
+    /// - 已经取消时返回 `Cancelled` 类型错误
+    ///
+    fn ffmpeg<S: AsRef<OsStr>>(&self, args: &[S], duration: f64) -> Result<()> {
+        let target = args
+            .last()
+            .map(|arg| PathBuf::from(arg.as_ref()))
+            .unwrap_or_default();
+        // 错误信息中记录实际执行的完整参数，可以直接复制后重新运行
+        let args: Vec<OsString> = PROGRESS_ARGS
+            .iter()
+            .map(OsString::from)
+            .chain(args.iter().map(|arg| arg.as_ref().to_owned()))
+            .collect();
+        let mut child = Command::new(&self.ffmpeg)
             .current_dir(&self.work_dir)
-            .args(args)
-            .output()?;
-        if !command.status.success() {
-            println!("{}", String::from_utf8(command.stderr).unwrap());
-            return Err(err_new!(Kind::Other, "FFmpeg command failed"));
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // stdout 和 stderr 需要同时读取，以免其中一个管道写满后阻塞，
+        // 主线程等待进程结束并检查取消令牌
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let waited = thread::scope(|scope| {
+            let stderr = scope.spawn(move || {
+                let mut buf = Vec::new();
+                let _ = stderr.read_to_end(&mut buf);
+                buf
+            });
+            scope.spawn(|| {
+                for line in BufReader::new(stdout)
+                    .lines()
+                    .map_while(std::io::Result::ok)
+                {
+                    if let Some(percent) = progress::parse_percent(&line, duration) {
+                        self.emit(Progress::Encoding {
+                            target: target.clone(),
+                            percent,
+                        });
+                    }
+                }
+            });
+            let status = loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if self.cancel.is_cancelled() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    self.check_cancelled()?;
+                }
+                thread::sleep(POLL_INTERVAL);
+            };
+            if status.success() {
+                return Ok(());
+            }
+            let stderr = stderr.join().unwrap_or_default();
+            Err(err_new_ffmpeg!(FfmpegError::new(&args, status, &stderr)))
+        });
+        if waited.is_err() {
+            let _ = std::fs::remove_file(self.work_dir.join(&target));
         }
-        Ok(())
+        waited
     }
 
     /// 合并多个文件为单个输出文件，使用ffmpeg的concat协议
@@ -317,49 +817,31 @@ impl BigImg<'_> {
     /// # Parameters
     /// - `results`: 需要合并的源文件路径列表
     /// - `save_name`: 合并后的输出文件路径
+    /// - `duration`: 合并后的视频时长（秒），用于计算进度百分比
     ///
     /// # Errors
     /// - 如果文件写入或 `FFmpeg` 命令执行失败，则返回 `Err`。
     ///
-    fn combain(&self, results: &mut [PathBuf], save_name: &Path) -> Result<()> {
-        // 构建ffmpeg concat协议要求的输入文件列表字符串
-        // 格式示例：file '/path/to/file1'\nfile '/path/to/file2'
-        let result_str =
-            results
-                .iter()
-                .fold(String::with_capacity(results.len() * 20), |mut init, s| {
-                    init.push_str(&format!("file {}\n", s.to_string_lossy()));
-                    init
-                });
-
-        // 将文件列表写入临时文本文件
+    fn combain(&self, results: &mut [PathBuf], save_name: &Path, duration: f64) -> Result<()> {
+        // 将文件列表写入临时文本文件，参数见 `concat_args`
         let list_file = self.work_dir.join("list.txt");
-        std::fs::write(&list_file, result_str)?;
-
-        // 调用ffmpeg执行合并操作参数说明：
-        // -f concat 指定concat分离器
-        // -i 输入文件列表
-        // -c copy 使用流拷贝模式（不重新编码）
-        // -y 覆盖输出文件
-        self.ffmpeg(&[
-            "-f",
-            "concat",
-            "-i",
-            &list_file.to_string_lossy(),
-            "-c",
-            "copy",
-            "-y",
-            &save_name.to_string_lossy(),
-        ])?;
-
-        println!("{} successed", save_name.to_string_lossy());
+        std::fs::write(&list_file, concat_list(results))?;
 
-        // 清理临时文件（包含两个步骤）：
-        // 1. 删除文件列表
-        // 2. 删除所有中间结果文件及其对应的png文件
+        self.emit(Progress::Concat);
+        let concated = self.ffmpeg(&plan::concat_args(save_name), duration);
         let _ = std::fs::remove_file(&list_file);
+        concated?;
+
+        println!("{} successed", save_name.display());
+
+        // 清理临时文件（包含两个步骤）：
+        // 1. 删除文件列表（合并后已删除）
+        // 2. 删除所有中间结果文件及其对应的png文件，启用缓存时保留中间结果文件以便复用
+        self.emit(Progress::Cleanup);
         for result in results {
-            let _ = std::fs::remove_file(self.work_dir.join(&result));
+            if !self.cache {
+                let _ = std::fs::remove_file(self.work_dir.join(&result));
+            }
             result.set_extension("png");
             let _ = std::fs::remove_file(self.work_dir.join(result));
         }
@@ -368,6 +850,52 @@ impl BigImg<'_> {
     }
 }
 
+/// 转换为传给 `FFmpeg` 的路径参数。
+///
+/// 相对路径前加上 `./`，避免以 `-` 开头的文件名被当作选项，
+/// 或者含有 `:` 的文件名被当作协议。
+fn ffmpeg_path(path: &Path) -> OsString {
+    if path.is_relative() && !path.starts_with(".") {
+        Path::new(".").join(path).into_os_string()
+    } else {
+        path.as_os_str().to_owned()
+    }
+}
+
+/// 生成 `FFmpeg` concat 分离器的文件列表。
+///
+/// 每个文件名用单引号包围，文件名中的单引号转义为 `'\''`，
+/// 文件名按原始字节写入，可以包含空格、中文等任意字符。
+fn concat_list(files: &[PathBuf]) -> Vec<u8> {
+    let mut list = Vec::with_capacity(files.len() * 20);
+    for file in files {
+        list.extend_from_slice(b"file '");
+        for &byte in ffmpeg_path(file).as_encoded_bytes() {
+            if byte == b'\'' {
+                list.extend_from_slice(b"'\\''");
+            } else {
+                list.push(byte);
+            }
+        }
+        list.extend_from_slice(b"'\n");
+    }
+    list
+}
+
+/// 从字体文件加载字体。
+///
+/// # Parameters
+/// - `path`: 字体文件路径。
+///
+/// # Errors
+/// - 如果文件读取失败，则返回 `Err`。
+/// - 如果字体解析失败，则返回 `InvalidFont` 类型的 `Err`。
+///
+pub fn load_font<P: AsRef<Path>>(path: P) -> Result<FontVec> {
+    let font_buf = std::fs::read(path.as_ref()).map_err(|e| err_new_io!(e))?;
+    FontVec::try_from_vec(font_buf).map_err(|e| err_new!(Kind::InvalidFont, &e.to_string()))
+}
+
 impl Debug for BigImg<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BigImg")
@@ -377,7 +905,10 @@ impl Debug for BigImg<'_> {
             .field("screen", &self.screen)
             .field("step", &self.step)
             .field("width_chunk", &self.width_chunk)
-            .field("overlap", &self.overlap)
+            .field("fit_width", &self.fit_width)
+            .field("strip_len", &self.strip_len())
+            .field("axis", &self.axis)
+            .field("rows", &self.rows)
             .field("text_background_color", &self.text_background_color)
             .field("text_color", &self.text_color)
             .field("max_scale", &self.max_scale)
@@ -385,11 +916,25 @@ impl Debug for BigImg<'_> {
             .field("text_up_h", &self.text_up_h)
             .field("text_down_h", &self.text_down_h)
             .field("font", &self.font)
+            .field("layout_scale", &self.layout_scale)
             .field("video_cover_time", &self.video_cover_time)
             .field("video_ending_time", &self.video_ending_time)
             .field("video_background_color", &self.video_background_color)
             .field("video_swip_speed", &self.video_swip_speed)
             .field("video_fps", &self.video_fps)
+            .field("pipeline", &self.pipeline)
+            .field("motion", &self.motion)
+            .field("direction", &self.direction)
+            .field("audio", &self.audio)
+            .field("subtitles", &self.subtitles)
+            .field("chapters", &self.chapters)
+            .field("output_format", &self.output_format)
+            .field("encoder", &self.encoder)
+            .field("workers", &self.workers)
+            .field("cache", &self.cache)
+            .field("ffmpeg", &self.ffmpeg)
+            .field("progress", &self.progress.is_some())
+            .field("cancel", &self.cancel.is_cancelled())
             .finish()
     }
 }
@@ -400,6 +945,9 @@ pub struct BigImgBuilder<'a> {
     screen: (u32, u32),
     step: u32,
     width_chunk: u32,
+    fit_width: Option<(u32, u32)>,
+    axis: Axis,
+    rows: u32,
     text_background_color: (Rgba<u8>, Rgba<u8>),
     text_color: Rgba<u8>,
     max_scale: f32,
@@ -409,8 +957,22 @@ pub struct BigImgBuilder<'a> {
     video_cover_time: u32,
     video_ending_time: u32,
     video_background_color: String,
-    video_swip_speed: u32,
+    video_swip_speed: SwipSpeed,
     video_fps: u32,
+    pipeline: Pipeline,
+    motion: Option<Box<dyn Motion>>,
+    direction: Direction,
+    audio: Vec<AudioTrack>,
+    subtitles: bool,
+    chapters: bool,
+    output_format: Option<OutputFormat>,
+    encoder: EncoderSettings,
+    workers: usize,
+    cache: bool,
+    ffmpeg: Option<PathBuf>,
+    progress: Option<ProgressFn<'a>>,
+    cancel: CancelToken,
+    preview: Option<Preview>,
 }
 
 impl<'a> BigImgBuilder<'a> {
@@ -431,6 +993,9 @@ impl<'a> BigImgBuilder<'a> {
             screen: (1920, 1080),
             step: 40,
             width_chunk: 480,
+            fit_width: None,
+            axis: Axis::Horizontal,
+            rows: 1,
             text_background_color: (Rgba([23, 150, 235, 255]), Rgba([44, 85, 153, 255])),
             text_color: Rgba([255, 255, 255, 255]),
             max_scale: 120.0,
@@ -440,8 +1005,22 @@ impl<'a> BigImgBuilder<'a> {
             video_cover_time: 3,
             video_ending_time: 3,
             video_background_color: String::from("white"),
-            video_swip_speed: 3,
+            video_swip_speed: SwipSpeed::SecondsPerChunk(3.0),
             video_fps: 60,
+            pipeline: Pipeline::Segments,
+            motion: None,
+            direction: Direction::Forward,
+            audio: Vec::new(),
+            subtitles: false,
+            chapters: false,
+            output_format: None,
+            encoder: EncoderSettings::default(),
+            workers: std::thread::available_parallelism().map_or(1, usize::from),
+            cache: true,
+            ffmpeg: None,
+            progress: None,
+            cancel: CancelToken::default(),
+            preview: None,
         }
     }
 
@@ -455,9 +1034,12 @@ impl<'a> BigImgBuilder<'a> {
     ///
     /// # Errors
     /// - 如果 `chunks` 为空，则返回 `Err`。
-    /// - 如果 `pic_h` 大于屏幕高度，则返回 `Err`。
-    /// - 如果屏幕宽度不能被 `width_chunk` 整除，则返回 `Err`。
+    /// - 如果 `video_fps` 为 0，则返回 `Err`。
+    /// - 如果 `pic_h` 或 `pic_h + text_up_h` 大于每个图像块垂直于滑动方向的长度，则返回 `Err`。
+    /// - 如果 `step` 列不足以覆盖一屏，则返回 `Err`。
+    /// - 如果设置了 `fit_width` 且读取图片尺寸失败，则返回 `Err`。
     /// - 如果字体加载失败，则返回 `Err`。
+    /// - 如果输出格式或编码参数无效，或者设置的输出格式不能包含音频和章节，则返回 `Err`。
     ///
     pub fn build(&mut self) -> Result<BigImg<'a>> {
         if !self.work_dir.exists() {
@@ -466,57 +1048,136 @@ impl<'a> BigImgBuilder<'a> {
         if self.chunks.is_empty() {
             return Err(err_new!(Kind::BigImgBuilderError, "chunks data is empty"));
         }
-        if self.pic_h > self.screen.1 {
+        if self.video_fps == 0 {
+            return Err(err_new!(
+                Kind::BigImgBuilderError,
+                "video_fps must be non-zero"
+            ));
+        }
+        // 横向滑动时垂直于滑动方向为屏幕高度，纵向滑动时为屏幕宽度；每列有 `rows` 个图像块
+        let cross = self.axis.split(self.screen).1 / self.rows;
+        let cross_name = match (self.axis, self.rows) {
+            (Axis::Horizontal, 1) => "height_screen",
+            (Axis::Vertical, 1) => "width_screen",
+            (Axis::Horizontal, _) => "height_screen / rows",
+            (Axis::Vertical, _) => "width_screen / rows",
+        };
+        if self.pic_h > cross {
             return Err(err_new!(
                 Kind::BigImgBuilderError,
                 &format!(
-                    "err:\n{},\n{}\n pic_h > height_screen; {} > {}",
+                    "err:\n{},\n{}\n pic_h > {cross_name}; {} > {cross}",
                     file!(),
                     line!(),
                     self.pic_h,
-                    self.screen.1
                 )
             ));
         }
-        if self.screen.0 % self.width_chunk != 0 {
+        if self.pic_h + self.text_up_h > cross {
             return Err(err_new!(
                 Kind::BigImgBuilderError,
                 &format!(
-                    "err: width_screen % width_chunk != 0; {} % {} != 0",
-                    self.screen.0, self.width_chunk
+                    "err: pic_h + text_up_h > {cross_name}; {} + {} > {cross}",
+                    self.pic_h, self.text_up_h,
                 )
             ));
         }
-        self.step = self.step.min(u32::try_from(self.chunks.len()).unwrap_or(0));
-        Ok(BigImg {
+        let columns = self.chunks.len().div_ceil(self.rows as usize);
+        self.step = self.step.min(u32::try_from(columns).unwrap_or(0));
+        let streams = (!self.audio.is_empty(), self.chapters);
+        let format_checked = self.output_format.map(|format| {
+            format
+                .check()
+                .and_then(|()| format.check_streams(streams.0, streams.1))
+        });
+        if let Some(Err(e)) = format_checked {
+            return Err(err_new!(Kind::BigImgBuilderError, &format!("err: {e}")));
+        }
+        if let Err(e) = self.encoder.check() {
+            return Err(err_new!(Kind::BigImgBuilderError, &format!("err: {e}")));
+        }
+        if let Some(Err(e)) = self.preview.as_ref().map(Preview::check) {
+            return Err(err_new!(Kind::BigImgBuilderError, &format!("err: {e}")));
+        }
+        let motion = self
+            .motion
+            .take()
+            .unwrap_or_else(|| Box::new(MotionProfile::Linear));
+        if self.pipeline == Pipeline::Segments && !motion.is_linear() {
+            return Err(err_new!(
+                Kind::BigImgBuilderError,
+                &format!("err: motion {motion:?} requires Pipeline::Stream")
+            ));
+        }
+        let mut si = BigImg {
             work_dir: self.work_dir.clone(),
             chunks: self.chunks,
             screen: self.screen,
             step: self.step,
             width_chunk: self.width_chunk,
-            overlap: self.screen.0 / self.width_chunk,
+            fit_width: self.fit_width,
+            column_offsets: Vec::new(),
+            axis: self.axis,
+            rows: self.rows,
             text_background_color: self.text_background_color,
             text_color: self.text_color,
             max_scale: self.max_scale,
             pic_h: self.pic_h,
             text_up_h: self.text_up_h,
-            text_down_h: self.screen.1 - self.pic_h - self.text_up_h,
-            font: self.font.take().unwrap_or({
-                let font_buf = std::fs::read("./src/swiping_img/MiSans-Demibold.ttf")
-                    .map_err(|e| err_new_io!(e))?;
-                FontVec::try_from_vec(font_buf)
-                    .map_err(|e| err_new!(Kind::InvalidFont, &e.to_string()))?
-            }),
+            text_down_h: cross - self.pic_h - self.text_up_h,
+            font: match self.font.take() {
+                Some(font) => font,
+                None => load_font("./src/swiping_img/MiSans-Demibold.ttf")?,
+            },
+            layout_scale: 1.0,
             video_cover_time: self.video_cover_time,
             video_ending_time: self.video_ending_time,
             video_background_color: self.video_background_color.clone(),
             video_swip_speed: self.video_swip_speed,
             video_fps: self.video_fps,
-        })
+            pipeline: self.pipeline,
+            motion,
+            direction: self.direction,
+            audio: self.audio.clone(),
+            subtitles: self.subtitles,
+            chapters: self.chapters,
+            output_format: self.output_format,
+            encoder: self.encoder.clone(),
+            workers: self.workers,
+            cache: self.cache,
+            ffmpeg: self.resolve_ffmpeg(),
+            progress: self.progress.take(),
+            cancel: self.cancel.clone(),
+            style_hash: 0,
+        };
+        si.column_offsets = si.layout()?;
+        si.check_step()?;
+        if let Some(preview) = &self.preview {
+            si.apply_preview(preview)?;
+        }
+        // 禁用缓存时也计算，清理缓存和复用分段视频都依赖缓存键
+        si.style_hash = si.style_hash();
+        Ok(si)
+    }
+
+    /// `FFmpeg` 的路径，依次使用 `ffmpeg` 参数、环境变量 `FFMPEG_PATH` 和 `ffmpeg`。
+    ///
+    /// 包含目录的相对路径转换为绝对路径，因为 `FFmpeg` 在工作路径中运行。
+    fn resolve_ffmpeg(&self) -> PathBuf {
+        let ffmpeg = self
+            .ffmpeg
+            .clone()
+            .or_else(|| std::env::var_os(FFMPEG_ENV).map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("ffmpeg"));
+        if ffmpeg.components().count() > 1 {
+            std::path::absolute(&ffmpeg).unwrap_or(ffmpeg)
+        } else {
+            ffmpeg
+        }
     }
 }
 
-impl BigImgBuilder<'_> {
+impl<'a> BigImgBuilder<'a> {
     /// 设置屏幕分辨率。
     ///
     /// # Parameters
@@ -562,6 +1223,25 @@ impl BigImgBuilder<'_> {
         self
     }
 
+    /// 设置图像块沿滑动方向的长度随图片的宽高比变化
+    ///
+    /// # Parameters
+    /// - `min`: 最小长度
+    /// - `max`: 最大长度。图像块的长度使图片恰好填满图片区域，并限制在 `min..=max` 范围内，
+    ///   同一列的图像块取其中最长的长度；设置后不再使用 `width_chunk`，构建时需要读取图片的尺寸
+    ///
+    /// # Panics
+    /// - 如果 `min` 为零或大于 `max`，程序将 panic
+    ///
+    pub fn fit_width(&mut self, min: u32, max: u32) -> &mut Self {
+        assert!(
+            min != 0 && min <= max,
+            "Fit width must satisfy 0 < min <= max."
+        );
+        self.fit_width = Some((min, max));
+        self
+    }
+
     /// 设置文本颜色
     ///
     /// # Parameters
@@ -620,6 +1300,16 @@ impl BigImgBuilder<'_> {
         self
     }
 
+    /// 设置文本渲染使用的字体
+    ///
+    /// # Parameters
+    /// - `font`: 字体，未设置时使用默认字体 `MiSans-Demibold.ttf`
+    ///
+    pub fn font(&mut self, font: FontVec) -> &mut Self {
+        self.font = Some(font);
+        self
+    }
+
     /// 设置视频封面时间
     ///
     /// # Parameters
@@ -640,6 +1330,21 @@ impl BigImgBuilder<'_> {
         self
     }
 
+    /// 设置每列图像块的数量
+    ///
+    /// # Parameters
+    /// - `rows`: 每列图像块的数量，默认为 `1`。同一列的图像块沿垂直于滑动方向平分屏幕，
+    ///   `pic_h` 和 `text_up_h` 不能超过每个图像块的长度；`step` 以列为单位
+    ///
+    /// # Panics
+    /// - 如果 `rows` 为零，程序将 panic
+    ///
+    pub fn rows(&mut self, rows: u32) -> &mut Self {
+        assert_ne!(rows, 0, "Rows must be non-zero.");
+        self.rows = rows;
+        self
+    }
+
     /// 设置视频背景颜色
     ///
     /// # Parameters
@@ -653,9 +1358,19 @@ impl BigImgBuilder<'_> {
     /// 设置视频滑动速度
     ///
     /// # Parameters
-    /// - `video_swip_speed`: 视频滑动速度，使用 `u32` 类型表示
+    /// - `video_swip_speed`: 视频滑动速度，`f64` 表示滑动一个图像块所需的秒数，图像块长度不同时按平均长度计算，
+    ///   也可以使用 `SwipSpeed::PixelsPerSecond` 表示每秒滑动的像素数
+    ///
+    /// # Panics
+    /// - 如果速度不是有限的正数，程序将 panic
     ///
-    pub fn video_swip_speed(&mut self, video_swip_speed: u32) -> &mut Self {
+    pub fn video_swip_speed(&mut self, video_swip_speed: impl Into<SwipSpeed>) -> &mut Self {
+        let video_swip_speed = video_swip_speed.into();
+        let (SwipSpeed::SecondsPerChunk(v) | SwipSpeed::PixelsPerSecond(v)) = video_swip_speed;
+        assert!(
+            v.is_finite() && v > 0.0,
+            "Swip speed must be a positive number."
+        );
         self.video_swip_speed = video_swip_speed;
         self
     }
@@ -669,4 +1384,294 @@ impl BigImgBuilder<'_> {
         self.video_fps = video_fps;
         self
     }
+
+    /// 设置滑动方向
+    ///
+    /// # Parameters
+    /// - `axis`: 滑动方向，默认为 `Axis::Horizontal`；`Axis::Vertical` 时 `width_chunk` 为图像块的高度，
+    ///   `pic_h` 和 `text_up_h` 为图片和上方文本的宽度
+    ///
+    pub fn axis(&mut self, axis: Axis) -> &mut Self {
+        self.axis = axis;
+        self
+    }
+
+    /// 设置视频生成方式
+    ///
+    /// # Parameters
+    /// - `pipeline`: 视频生成方式，默认为 `Pipeline::Segments`
+    ///
+    pub fn pipeline(&mut self, pipeline: Pipeline) -> &mut Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// 设置滑动阶段的运动曲线
+    ///
+    /// # Parameters
+    /// - `motion`: 运动曲线，默认为 `MotionProfile::Linear`；非匀速运动需要使用 `Pipeline::Stream`
+    ///
+    pub fn motion(&mut self, motion: impl Motion + 'static) -> &mut Self {
+        self.motion = Some(Box::new(motion));
+        self
+    }
+
+    /// 设置滑动方向
+    ///
+    /// # Parameters
+    /// - `direction`: 滑动方向，默认为 `Direction::Forward`；`Direction::PingPong` 时滑动阶段的时长加倍
+    ///
+    pub fn direction(&mut self, direction: Direction) -> &mut Self {
+        self.direction = direction;
+        self
+    }
+
+    /// 添加音频轨道，多个轨道会混合在一起
+    ///
+    /// # Parameters
+    /// - `track`: 音频轨道
+    ///
+    pub fn audio(&mut self, track: AudioTrack) -> &mut Self {
+        self.audio.push(track);
+        self
+    }
+
+    /// 设置是否生成字幕文件
+    ///
+    /// # Parameters
+    /// - `subtitles`: 为 `true` 时在视频旁生成同名的 `.srt` 和 `.vtt` 字幕文件，
+    ///   标记每个图像块到达屏幕中心的时间
+    ///
+    pub fn subtitles(&mut self, subtitles: bool) -> &mut Self {
+        self.subtitles = subtitles;
+        self
+    }
+
+    /// 设置是否写入章节
+    ///
+    /// # Parameters
+    /// - `chapters`: 为 `true` 时在视频中写入章节，标记每个图像块到达屏幕中心的时间
+    ///
+    pub fn chapters(&mut self, chapters: bool) -> &mut Self {
+        self.chapters = chapters;
+        self
+    }
+
+    /// 设置输出格式
+    ///
+    /// # Parameters
+    /// - `output_format`: 输出格式，未设置时根据输出文件的扩展名推断，无法推断时为 `OutputFormat::Mp4`
+    ///
+    pub fn output_format(&mut self, output_format: OutputFormat) -> &mut Self {
+        self.output_format = Some(output_format);
+        self
+    }
+
+    /// 设置视频编码参数
+    ///
+    /// # Parameters
+    /// - `encoder`: 视频编码参数，默认为 `libx264`、`yuv420p`、`fast`
+    ///
+    pub fn encoder(&mut self, encoder: EncoderSettings) -> &mut Self {
+        self.encoder = encoder;
+        self
+    }
+
+    /// 设置并行绘制图像块的线程数
+    ///
+    /// # Parameters
+    /// - `workers`: 线程数，默认为可用的 CPU 核心数
+    ///
+    /// # Panics
+    /// - 如果 `workers` 为零，程序将 panic
+    ///
+    pub fn workers(&mut self, workers: usize) -> &mut Self {
+        assert_ne!(workers, 0, "Workers must be non-zero.");
+        self.workers = workers;
+        self
+    }
+
+    /// 设置是否缓存绘制好的图像块
+    ///
+    /// # Parameters
+    /// - `cache`: 为 `true` 时绘制好的图像块保存在工作路径的 `cache` 目录中，
+    ///   图片内容、文本和样式参数都未改变的图像块直接读取缓存，默认为 `true`。
+    ///   同时控制分段视频的复用：为 `false` 时分段视频在合并后删除，也不读取或写入分段视频清单
+    ///
+    pub fn cache(&mut self, cache: bool) -> &mut Self {
+        self.cache = cache;
+        self
+    }
+
+    /// 设置 `FFmpeg` 可执行文件的路径
+    ///
+    /// # Parameters
+    /// - `ffmpeg`: `FFmpeg` 的路径，只有文件名时在 `PATH` 中查找。
+    ///   未设置时依次使用环境变量 `FFMPEG_PATH` 和 `PATH` 中的 `ffmpeg`
+    ///
+    pub fn ffmpeg<P: Into<PathBuf>>(&mut self, ffmpeg: P) -> &mut Self {
+        self.ffmpeg = Some(ffmpeg.into());
+        self
+    }
+
+    /// 设置接收进度事件的回调
+    ///
+    /// # Parameters
+    /// - `progress`: 每个图像块绘制完成、`FFmpeg` 编码进度更新、分段视频生成完成
+    ///   以及合并、转换和清理开始时调用，可能在多个线程中同时调用。
+    ///   需要在其他线程中处理事件时，可以在回调中把事件发送到 `std::sync::mpsc::Sender`
+    ///
+    pub fn progress<F>(&mut self, progress: F) -> &mut Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'a,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// 设置取消令牌
+    ///
+    /// # Parameters
+    /// - `cancel`: 取消令牌，保留它的克隆并在任意线程中调用 `cancel` 即可停止生成。
+    ///   正在运行的 `FFmpeg` 进程会被终止，未完成的输出文件和中间文件会被删除
+    ///
+    pub fn cancel(&mut self, cancel: CancelToken) -> &mut Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// 设置低分辨率快速预览
+    ///
+    /// # Parameters
+    /// - `preview`: 预览参数，构建时按比例缩小屏幕、图像块和字体，降低帧率并使用更快的编码预设，
+    ///   其余参数仍按完整视频校验
+    ///
+    pub fn preview(&mut self, preview: Preview) -> &mut Self {
+        self.preview = Some(preview);
+        self
+    }
+}
+
+/// 测试用的图像块，每个文本对应一个图像块。
+///
+/// 图片路径为 `Cargo.toml`，使用固定宽度时构建和缓存键只检查路径和读取文件内容，不解码图片。
+#[cfg(test)]
+fn test_chunks(texts: &[&str]) -> Vec<Chunk> {
+    texts
+        .iter()
+        .map(|&text| {
+            Chunk::new(
+                PathBuf::from("Cargo.toml"),
+                vec![text.into()],
+                vec!["b".into()],
+            )
+            .unwrap()
+        })
+        .collect()
+}
+
+/// 工作路径为系统临时目录的 `BigImgBuilder`
+#[cfg(test)]
+fn test_builder(chunks: &[Chunk]) -> BigImgBuilder<'_> {
+    BigImgBuilder::new(&std::env::temp_dir(), chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat_list() {
+        let files = [
+            PathBuf::from("00.mp4"),
+            PathBuf::from("-片段 1.mp4"),
+            PathBuf::from("it's:a.mp4"),
+            PathBuf::from("/tmp/工作 路径/ending.mp4"),
+        ];
+        assert_eq!(
+            String::from_utf8(concat_list(&files)).unwrap(),
+            "file './00.mp4'\n\
+             file './-片段 1.mp4'\n\
+             file './it'\\''s:a.mp4'\n\
+             file '/tmp/工作 路径/ending.mp4'\n"
+        );
+    }
+
+    #[test]
+    fn test_build_rows() {
+        let chunks = test_chunks(&["a"]);
+        // 默认的 `pic_h + text_up_h` 为 734，每列 2 个图像块时每个图像块只有 540
+        let built = test_builder(&chunks).rows(2).build();
+        assert!(built.is_err_and(|e| e.to_string().contains("pic_h + text_up_h")));
+        assert!(test_builder(&chunks)
+            .rows(2)
+            .pic_h(300)
+            .text_up_h(120)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_build_zero_fps() {
+        let chunks = test_chunks(&["a"]);
+        let built = test_builder(&chunks).video_fps(0).build();
+        assert!(built.is_err_and(|e| e.to_string().contains("video_fps")));
+    }
+
+    #[test]
+    fn test_build_format_streams() {
+        let chunks = test_chunks(&["a"]);
+        let built = test_builder(&chunks)
+            .output_format(OutputFormat::gif())
+            .chapters(true)
+            .build();
+        assert!(built.is_err_and(|e| e.to_string().contains("chapters")));
+        // 未设置输出格式时在生成前根据文件名检查
+        let si = test_builder(&chunks).chapters(true).build().unwrap();
+        assert!(si.plan("result.gif").is_err());
+        assert!(si.plan("result.webm").is_ok());
+    }
+
+    #[test]
+    fn test_cover_key() {
+        let chunks = test_chunks(&["a"; 12]);
+        let cover_key = |direction| {
+            let si = test_builder(&chunks).direction(direction).build().unwrap();
+            let timeline = Timeline::new(&si);
+            let segments = timeline.segments(&si.divide(), &si.column_offsets, si.rows);
+            let videos = si.segment_videos(&timeline, &segments);
+            let cover = videos
+                .iter()
+                .find(|video| video.part == SegmentPart::Cover)
+                .unwrap();
+            let segment = &segments[cover.segment];
+            let params = si.segment_params(&timeline, segment, cover);
+            si.segment_key(segment.chunks.clone(), &params).unwrap()
+        };
+        // 图像块相同时，封面仍然随滑动方向从长图的不同端开始
+        assert_ne!(cover_key(Direction::Forward), cover_key(Direction::Reverse));
+        assert_eq!(
+            cover_key(Direction::Forward),
+            cover_key(Direction::PingPong)
+        );
+    }
+
+    #[test]
+    fn test_axis() {
+        assert_eq!(Axis::Horizontal.split((1920, 1080)), (1920, 1080));
+        assert_eq!(Axis::Vertical.split((1080, 1920)), (1920, 1080));
+        assert_eq!(Axis::Vertical.size(480, 0), (0, 480));
+        assert_eq!("vertical".parse(), Ok(Axis::Vertical));
+        assert!("up".parse::<Axis>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let file = PathBuf::from(OsStr::from_bytes(b"\xff\xfe.mp4"));
+        assert_eq!(ffmpeg_path(&file).as_bytes(), b"./\xff\xfe.mp4");
+        assert_eq!(concat_list(&[file]), b"file './\xff\xfe.mp4'\n");
+    }
 }
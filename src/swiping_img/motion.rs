@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Debug, Display},
+    str::FromStr,
+};
+
+/// 滑动速度
+///
+/// * `SecondsPerChunk`: 滑动一个图像块所需的秒数，图像块长度不同时按平均长度计算。
+/// * `PixelsPerSecond`: 每秒滑动的像素数。
+///
+/// 项目文件和命令行中用数字或 `"2.5s"` 表示 `SecondsPerChunk`，用 `"300px/s"` 表示 `PixelsPerSecond`。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SwipSpeedRepr", into = "String")]
+pub enum SwipSpeed {
+    SecondsPerChunk(f64),
+    PixelsPerSecond(f64),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SwipSpeedRepr {
+    Seconds(f64),
+    Text(String),
+}
+
+impl SwipSpeed {
+    /// 换算为每秒滑动的像素数，`chunk_len` 为图像块沿滑动方向的（平均）长度
+    #[must_use]
+    pub fn pixels_per_second(self, chunk_len: f64) -> f64 {
+        match self {
+            Self::SecondsPerChunk(seconds) => chunk_len / seconds,
+            Self::PixelsPerSecond(pixels) => pixels,
+        }
+    }
+
+    fn value(self) -> f64 {
+        match self {
+            Self::SecondsPerChunk(v) | Self::PixelsPerSecond(v) => v,
+        }
+    }
+
+    /// 检查速度是否为正的有限数
+    fn check(self) -> std::result::Result<Self, String> {
+        if self.value().is_finite() && self.value() > 0.0 {
+            Ok(self)
+        } else {
+            Err(format!(
+                "swip speed must be positive, got `{}`",
+                self.value()
+            ))
+        }
+    }
+}
+
+impl From<f64> for SwipSpeed {
+    fn from(seconds: f64) -> Self {
+        Self::SecondsPerChunk(seconds)
+    }
+}
+
+impl FromStr for SwipSpeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(pixels) = s.strip_suffix("px/s") {
+            pixels.trim().parse().map(Self::PixelsPerSecond)
+        } else {
+            s.strip_suffix('s')
+                .unwrap_or(s)
+                .trim()
+                .parse()
+                .map(Self::SecondsPerChunk)
+        }
+        .map_err(|e| e.to_string())
+        .and_then(Self::check)
+        .map_err(|e| format!("`{s}`: {e}"))
+    }
+}
+
+impl TryFrom<SwipSpeedRepr> for SwipSpeed {
+    type Error = String;
+
+    fn try_from(value: SwipSpeedRepr) -> std::result::Result<Self, Self::Error> {
+        match value {
+            SwipSpeedRepr::Seconds(seconds) => Self::SecondsPerChunk(seconds).check(),
+            SwipSpeedRepr::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl Display for SwipSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SecondsPerChunk(seconds) => write!(f, "{seconds}s"),
+            Self::PixelsPerSecond(pixels) => write!(f, "{pixels}px/s"),
+        }
+    }
+}
+
+impl From<SwipSpeed> for String {
+    fn from(value: SwipSpeed) -> Self {
+        value.to_string()
+    }
+}
+
+/// 滑动方向
+///
+/// * `Forward`: 从长图开头滑动到末尾，封面为开头，结尾为末尾。
+/// * `Reverse`: 从长图末尾滑动到开头，封面为末尾，结尾为开头。
+/// * `PingPong`: 从长图开头滑动到末尾后再滑动回开头，封面和结尾都为开头，滑动阶段的时长加倍。
+///
+/// 项目文件和命令行中用 `"forward"`、`"reverse"` 和 `"ping-pong"` 表示。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    PingPong,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "forward" => Ok(Self::Forward),
+            "reverse" => Ok(Self::Reverse),
+            "ping-pong" => Ok(Self::PingPong),
+            _ => Err(format!(
+                "expected `forward`, `reverse` or `ping-pong`, got `{s}`"
+            )),
+        }
+    }
+}
+
+/// 滑动阶段的基本参数
+///
+/// # Parameters
+///
+/// * `distance`: 整个滑动阶段移动的像素数。
+/// * `speed`: 匀速滑动时的速度（像素每秒）。
+/// * `stride`: 每个图像块的（平均）长度（像素），用于按图像块停顿的运动曲线。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scroll {
+    pub distance: f64,
+    pub speed: f64,
+    pub stride: f64,
+}
+
+/// 滑动阶段的运动曲线
+///
+/// 时间轴按帧采样 [`Motion::position`] 得到每一帧的偏移量，实现该 trait 即可自定义运动方式。
+pub trait Motion: Debug + Send + Sync {
+    /// 滑动阶段的总时长（秒）。
+    fn duration(&self, scroll: &Scroll) -> f64;
+
+    /// 滑动阶段开始 `t` 秒后的偏移量（像素），应在 `0..=scroll.distance` 范围内单调不减。
+    fn position(&self, t: f64, scroll: &Scroll) -> f64;
+
+    /// 是否为匀速运动。匀速运动的偏移量可以直接由 `FFmpeg` 的 `overlay` 表达式计算。
+    fn is_linear(&self) -> bool {
+        false
+    }
+}
+
+/// 内置的运动曲线
+///
+/// * `Linear`: 匀速滑动。
+/// * `Ease`: 开始时用 `ease_in` 秒从静止加速到匀速，结束时用 `ease_out` 秒减速到静止。
+/// * `StepHold`: 每次匀速滑动一个图像块，然后停顿 `hold` 秒。
+///
+/// 所有时间都必须是非负的有限数。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "kebab-case",
+    deny_unknown_fields,
+    try_from = "MotionProfileRepr"
+)]
+pub enum MotionProfile {
+    #[default]
+    Linear,
+    Ease {
+        #[serde(default)]
+        ease_in: f64,
+        #[serde(default)]
+        ease_out: f64,
+    },
+    StepHold {
+        hold: f64,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+enum MotionProfileRepr {
+    Linear,
+    Ease {
+        #[serde(default)]
+        ease_in: f64,
+        #[serde(default)]
+        ease_out: f64,
+    },
+    StepHold {
+        hold: f64,
+    },
+}
+
+impl MotionProfile {
+    /// 检查所有时间是否为非负的有限数
+    fn check(self) -> std::result::Result<Self, String> {
+        let times: &[(&str, f64)] = match self {
+            Self::Linear => &[],
+            Self::Ease { ease_in, ease_out } => &[("ease_in", ease_in), ("ease_out", ease_out)],
+            Self::StepHold { hold } => &[("hold", hold)],
+        };
+        for &(name, time) in times {
+            if !(time.is_finite() && time >= 0.0) {
+                return Err(format!(
+                    "`{name}` must be a non-negative number of seconds, got `{time}`"
+                ));
+            }
+        }
+        Ok(self)
+    }
+}
+
+impl Motion for MotionProfile {
+    fn duration(&self, scroll: &Scroll) -> f64 {
+        let Scroll {
+            distance,
+            speed,
+            stride,
+        } = *scroll;
+        match *self {
+            Self::Linear => distance / speed,
+            Self::Ease { ease_in, ease_out } => {
+                let (ease_in, ease_out) = ease_times(ease_in, ease_out, scroll);
+                ease_in + ease_out + (distance - speed * (ease_in + ease_out) / 2.0) / speed
+            }
+            Self::StepHold { hold } => {
+                let steps = (distance / stride).ceil();
+                distance / speed + (steps - 1.0).max(0.0) * hold
+            }
+        }
+    }
+
+    fn position(&self, t: f64, scroll: &Scroll) -> f64 {
+        let Scroll {
+            distance,
+            speed,
+            stride,
+        } = *scroll;
+        let t = t.max(0.0);
+        let position = match *self {
+            Self::Linear => speed * t,
+            Self::Ease { ease_in, ease_out } => {
+                let (ease_in, ease_out) = ease_times(ease_in, ease_out, scroll);
+                let total = self.duration(scroll);
+                if t >= total {
+                    distance
+                } else if t < ease_in {
+                    speed * t * t / (2.0 * ease_in)
+                } else if t < total - ease_out {
+                    speed * ease_in / 2.0 + speed * (t - ease_in)
+                } else {
+                    let rest = (total - t).max(0.0);
+                    distance - speed * rest * rest / (2.0 * ease_out)
+                }
+            }
+            Self::StepHold { hold } => {
+                let slide = stride / speed;
+                let cycle = slide + hold;
+                let i = (t / cycle).floor();
+                i * stride + (speed * (t - i * cycle)).min(stride)
+            }
+        };
+        position.clamp(0.0, distance)
+    }
+
+    fn is_linear(&self) -> bool {
+        match *self {
+            Self::Linear => true,
+            Self::Ease { ease_in, ease_out } => ease_in <= 0.0 && ease_out <= 0.0,
+            Self::StepHold { hold } => hold <= 0.0,
+        }
+    }
+}
+
+/// 加速和减速所用的时间，距离不足以完成加减速时按比例缩短
+fn ease_times(ease_in: f64, ease_out: f64, scroll: &Scroll) -> (f64, f64) {
+    let (ease_in, ease_out) = (ease_in.max(0.0), ease_out.max(0.0));
+    let ramp = scroll.speed * (ease_in + ease_out) / 2.0;
+    if ramp > scroll.distance && ramp > 0.0 {
+        let scale = scroll.distance / ramp;
+        (ease_in * scale, ease_out * scale)
+    } else {
+        (ease_in, ease_out)
+    }
+}
+
+impl FromStr for MotionProfile {
+    type Err = String;
+
+    /// 解析 `linear`、`ease:IN,OUT`、`ease-in:IN`、`ease-out:OUT`、`step-hold:HOLD` 格式的运动曲线，时间单位为秒
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, args) = s.split_once(':').unwrap_or((s, ""));
+        let args = args
+            .split(',')
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| v.trim().parse::<f64>().map_err(|e| format!("`{v}`: {e}")))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        match (name, args.as_slice()) {
+            ("linear", []) => Ok(Self::Linear),
+            ("ease", &[ease_in, ease_out]) => Ok(Self::Ease { ease_in, ease_out }),
+            ("ease-in", &[ease_in]) => Ok(Self::Ease {
+                ease_in,
+                ease_out: 0.0,
+            }),
+            ("ease-out", &[ease_out]) => Ok(Self::Ease {
+                ease_in: 0.0,
+                ease_out,
+            }),
+            ("step-hold", &[hold]) => Ok(Self::StepHold { hold }),
+            _ => Err(format!(
+                "expected `linear`, `ease:IN,OUT`, `ease-in:IN`, `ease-out:OUT` or `step-hold:HOLD`, got `{s}`"
+            )),
+        }
+        .and_then(Self::check)
+        .map_err(|e| format!("`{s}`: {e}"))
+    }
+}
+
+impl TryFrom<MotionProfileRepr> for MotionProfile {
+    type Error = String;
+
+    fn try_from(value: MotionProfileRepr) -> std::result::Result<Self, Self::Error> {
+        match value {
+            MotionProfileRepr::Linear => Self::Linear,
+            MotionProfileRepr::Ease { ease_in, ease_out } => Self::Ease { ease_in, ease_out },
+            MotionProfileRepr::StepHold { hold } => Self::StepHold { hold },
+        }
+        .check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCROLL: Scroll = Scroll {
+        distance: 1440.0,
+        speed: 160.0,
+        stride: 480.0,
+    };
+
+    #[test]
+    fn test_profiles() {
+        let linear = MotionProfile::Linear;
+        assert_eq!(linear.duration(&SCROLL), 9.0);
+        assert_eq!(linear.position(4.5, &SCROLL), 720.0);
+
+        let ease: MotionProfile = "ease:2,1".parse().unwrap();
+        assert_eq!(ease.duration(&SCROLL), 10.5);
+        assert_eq!(ease.position(1.0, &SCROLL), 40.0);
+        assert_eq!(ease.position(2.0, &SCROLL), 160.0);
+        assert_eq!(ease.position(10.5, &SCROLL), 1440.0);
+
+        let step: MotionProfile = "step-hold:2".parse().unwrap();
+        assert_eq!(step.duration(&SCROLL), 13.0);
+        assert_eq!(step.position(3.0, &SCROLL), 480.0);
+        assert_eq!(step.position(4.9, &SCROLL), 480.0);
+        assert_eq!(step.position(6.5, &SCROLL), 720.0);
+
+        assert!("ease:1".parse::<MotionProfile>().is_err());
+        assert!("step-hold:-1".parse::<MotionProfile>().is_err());
+        assert!("step-hold:NaN".parse::<MotionProfile>().is_err());
+        assert!("ease:inf,1".parse::<MotionProfile>().is_err());
+        assert_eq!(
+            "step-hold:0".parse(),
+            Ok(MotionProfile::StepHold { hold: 0.0 })
+        );
+
+        let step: MotionProfile = serde_json::from_str(r#"{"type":"step-hold","hold":2}"#).unwrap();
+        assert_eq!(step, MotionProfile::StepHold { hold: 2.0 });
+        assert!(
+            serde_json::from_str::<MotionProfile>(r#"{"type":"step-hold","hold":-1}"#).is_err()
+        );
+        assert!(
+            serde_json::from_str::<MotionProfile>(r#"{"type":"ease","ease_out":-0.5}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_swip_speed() {
+        assert_eq!("7".parse(), Ok(SwipSpeed::SecondsPerChunk(7.0)));
+        assert_eq!("2.5s".parse(), Ok(SwipSpeed::SecondsPerChunk(2.5)));
+        assert_eq!("300px/s".parse(), Ok(SwipSpeed::PixelsPerSecond(300.0)));
+        assert!("0".parse::<SwipSpeed>().is_err());
+        assert!("fast".parse::<SwipSpeed>().is_err());
+        assert_eq!(
+            SwipSpeed::SecondsPerChunk(7.0).pixels_per_second(480.0),
+            480.0 / 7.0
+        );
+        let speed: SwipSpeed = serde_json::from_str("1.5").unwrap();
+        assert_eq!(speed, SwipSpeed::SecondsPerChunk(1.5));
+        let speed: SwipSpeed = serde_json::from_str("0.1").unwrap();
+        assert_eq!(speed, SwipSpeed::SecondsPerChunk(0.1));
+        assert!(serde_json::from_str::<SwipSpeed>("-2").is_err());
+        assert!(serde_json::from_str::<SwipSpeed>("0").is_err());
+        let speed: SwipSpeed = serde_json::from_str(r#""90px/s""#).unwrap();
+        assert_eq!(serde_json::to_string(&speed).unwrap(), r#""90px/s""#);
+    }
+
+    #[test]
+    fn test_monotonic() {
+        for motion in [
+            "linear",
+            "ease:3,5",
+            "ease-in:20",
+            "ease-out:1",
+            "step-hold:1.5",
+        ] {
+            let motion: MotionProfile = motion.parse().unwrap();
+            let duration = motion.duration(&SCROLL);
+            let positions: Vec<f64> = (0..=600)
+                .map(|i| motion.position(duration * f64::from(i) / 600.0, &SCROLL))
+                .collect();
+            assert!(positions.windows(2).all(|w| w[0] <= w[1]), "{motion:?}");
+            assert!(
+                (positions[600] - SCROLL.distance).abs() < 1e-6,
+                "{motion:?}"
+            );
+        }
+    }
+}
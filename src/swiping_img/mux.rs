@@ -0,0 +1,126 @@
+use super::{ffmpeg_path, subtitle, BigImg, Cue, OutputFormat, Timeline, VideoCodec};
+use crate::{err_new_io, error::Result, prelude::debug_print};
+use std::{ffi::OsString, path::Path};
+
+/// 章节文件名，位于工作路径中
+const CHAPTERS: &str = "chapters.txt";
+
+impl BigImg<'_> {
+    /// 将中间视频转换为输出格式，并添加音频轨道和章节。
+    ///
+    /// 多个音频轨道混合为一个音频流；输出格式为 `Mp4` 时视频流直接复制，否则重新编码。
+    /// 不支持音频的输出格式忽略音频轨道。
+    ///
+    /// # Parameters
+    /// - `timeline`: 视频时间轴，用于计算时长和滑动阶段开始的时间。
+    /// - `video_name`: 没有音频和章节的 `MP4` 中间视频文件名。
+    /// - `save_name`: 最终输出文件名。
+    /// - `format`: 输出格式。
+    /// - `chapters`: 需要写入的章节，为 `None` 时不写入章节。
+    ///
+    /// # Errors
+    /// - 如果音频文件路径无法解析或章节文件写入失败，则返回 `Err`。
+    /// - 如果 `FFmpeg` 命令执行失败，则返回 `Err`。
+    ///
+    pub(super) fn mux(
+        &self,
+        timeline: &Timeline,
+        video_name: &Path,
+        save_name: &Path,
+        format: OutputFormat,
+        chapters: Option<&[Cue]>,
+    ) -> Result<()> {
+        let args = self.mux_args(timeline, video_name, save_name, format, chapters.is_some())?;
+        let chapters_file = self.work_dir.join(CHAPTERS);
+        if let Some(chapters) = chapters {
+            std::fs::write(&chapters_file, subtitle::to_ffmetadata(chapters))
+                .map_err(|e| err_new_io!(e))?;
+        }
+        let result = self.ffmpeg(&args, timeline.duration());
+        if chapters.is_some() {
+            let _ = std::fs::remove_file(&chapters_file);
+        }
+        result?;
+        debug_print(format!("{save_name:?} muxed"));
+        Ok(())
+    }
+
+    /// 转换输出格式并混入音频和章节的 `FFmpeg` 参数。
+    ///
+    /// # Parameters
+    /// - `timeline`: 视频时间轴，用于计算时长和滑动阶段开始的时间。
+    /// - `video_name`: 没有音频和章节的 `MP4` 中间视频文件名。
+    /// - `save_name`: 最终输出文件名。
+    /// - `format`: 输出格式。
+    /// - `chapters`: 是否从工作路径中的章节文件读取章节。
+    ///
+    /// # Errors
+    /// - 如果音频文件路径无法解析，则返回 `Err`。
+    ///
+    pub(super) fn mux_args(
+        &self,
+        timeline: &Timeline,
+        video_name: &Path,
+        save_name: &Path,
+        format: OutputFormat,
+        chapters: bool,
+    ) -> Result<Vec<OsString>> {
+        let audio = if format.audio_codec().is_some() {
+            self.audio.as_slice()
+        } else {
+            &[]
+        };
+        let duration = timeline.duration();
+        let scroll_start = timeline.time(timeline.cover_frames);
+
+        // 所有输入必须在输出参数之前
+        let mut args: Vec<OsString> = vec!["-i".into(), ffmpeg_path(video_name)];
+        let mut filters = Vec::with_capacity(audio.len() + 1);
+        for (i, track) in audio.iter().enumerate() {
+            if track.looped {
+                args.extend(["-stream_loop".into(), "-1".into()]);
+            }
+            // FFmpeg 在工作路径中执行，相对路径需要以当前路径为基准
+            let path = std::path::absolute(&track.path)?;
+            args.extend(["-i".into(), ffmpeg_path(&path)]);
+            filters.push(track.filter(i + 1, scroll_start, duration));
+        }
+        if chapters {
+            // FFmpeg 在工作路径中执行，使用相对于工作路径的文件名
+            args.extend(["-i".into(), ffmpeg_path(Path::new(CHAPTERS))]);
+        }
+
+        args.extend(["-map".into(), "0:v".into()]);
+        if let (false, Some(codec)) = (audio.is_empty(), format.audio_codec()) {
+            let inputs: String = (1..=audio.len()).map(|i| format!("[a{i}]")).collect();
+            filters.push(format!(
+                "{inputs}amix=inputs={}:duration=longest:normalize=0[aout]",
+                audio.len()
+            ));
+            args.extend(
+                [
+                    "-filter_complex",
+                    &filters.join(";"),
+                    "-map",
+                    "[aout]",
+                    "-c:a",
+                    codec,
+                ]
+                .map(OsString::from),
+            );
+        }
+        if chapters {
+            let input = (audio.len() + 1).to_string();
+            args.extend(["-map_metadata", &input, "-map_chapters", &input].map(OsString::from));
+        }
+        // 中间视频已经是 VP9 编码时输出 WebM 不需要重新编码
+        if format == OutputFormat::WebM && self.encoder.codec == VideoCodec::LibvpxVp9 {
+            args.extend(["-c:v".into(), "copy".into()]);
+        } else {
+            args.extend(format.encode_args().into_iter().map(OsString::from));
+        }
+        args.extend(["-t".into(), format!("{duration:.3}").into(), "-y".into()]);
+        args.push(ffmpeg_path(&format.output_name(save_name)));
+        Ok(args)
+    }
+}
@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// 输出格式
+///
+/// 所有格式都使用同一条时间轴生成画面，只有最终编码方式不同。
+///
+/// * `Mp4`: `H.264` 编码的 `MP4` 视频，支持音频和章节。
+/// * `WebM`: `VP9` 编码的 `WebM` 视频，支持音频和章节。
+/// * `Gif`: 循环播放的 `GIF` 动图，`colors` 为调色板大小（`2..=256`），`dither` 为是否抖动。
+/// * `WebP`: 循环播放的 `WebP` 动图，`quality` 为质量（`0..=100`），`lossless` 为是否无损。
+/// * `Apng`: 循环播放的 `APNG` 动图。
+/// * `PngSequence`: `PNG` 图片序列，文件名中的 `%05d` 等格式替换为从 0 开始的帧序号。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum OutputFormat {
+    #[default]
+    Mp4,
+    #[serde(rename = "webm")]
+    WebM,
+    Gif {
+        #[serde(default = "default_colors")]
+        colors: u16,
+        #[serde(default = "default_dither")]
+        dither: bool,
+    },
+    #[serde(rename = "webp")]
+    WebP {
+        #[serde(default = "default_quality")]
+        quality: u8,
+        #[serde(default)]
+        lossless: bool,
+    },
+    Apng,
+    PngSequence,
+}
+
+fn default_colors() -> u16 {
+    256
+}
+
+fn default_dither() -> bool {
+    true
+}
+
+fn default_quality() -> u8 {
+    75
+}
+
+impl OutputFormat {
+    /// 根据文件扩展名推断输出格式
+    ///
+    /// `.png` 文件名中含有 `%` 时为图片序列，否则为 `APNG`。
+    /// 无法识别的扩展名返回 `None`。
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "mp4" => Some(Self::Mp4),
+            "webm" => Some(Self::WebM),
+            "gif" => Some(Self::gif()),
+            "webp" => Some(Self::webp()),
+            "apng" => Some(Self::Apng),
+            "png" if path.to_string_lossy().contains('%') => Some(Self::PngSequence),
+            "png" => Some(Self::Apng),
+            _ => None,
+        }
+    }
+
+    /// 使用默认参数的 `GIF` 格式
+    #[must_use]
+    pub fn gif() -> Self {
+        Self::Gif {
+            colors: default_colors(),
+            dither: default_dither(),
+        }
+    }
+
+    /// 使用默认参数的 `WebP` 格式
+    #[must_use]
+    pub fn webp() -> Self {
+        Self::WebP {
+            quality: default_quality(),
+            lossless: false,
+        }
+    }
+
+    /// 是否为视频容器，视频容器可以包含音频和章节
+    #[must_use]
+    pub fn is_video(self) -> bool {
+        matches!(self, Self::Mp4 | Self::WebM)
+    }
+
+    /// 音频编码器，不支持音频的格式返回 `None`
+    pub(super) fn audio_codec(self) -> Option<&'static str> {
+        match self {
+            Self::Mp4 => Some("aac"),
+            Self::WebM => Some("libopus"),
+            _ => None,
+        }
+    }
+
+    /// 将 `H.264` 编码的中间视频转换为该格式时使用的 `FFmpeg` 输出参数
+    pub(super) fn encode_args(self) -> Vec<String> {
+        match self {
+            Self::Mp4 => vec!["-c:v".into(), "copy".into()],
+            Self::WebM => ["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32"]
+                .map(String::from)
+                .to_vec(),
+            Self::Gif { colors, dither } => vec![
+                "-vf".into(),
+                format!(
+                    "split[a][b];[a]palettegen=max_colors={colors}:stats_mode=diff[p];\
+                     [b][p]paletteuse=dither={}",
+                    if dither { "sierra2_4a" } else { "none" }
+                ),
+                "-loop".into(),
+                "0".into(),
+            ],
+            Self::WebP { quality, lossless } => vec![
+                "-c:v".into(),
+                "libwebp".into(),
+                "-lossless".into(),
+                u8::from(lossless).to_string(),
+                "-quality".into(),
+                quality.to_string(),
+                "-loop".into(),
+                "0".into(),
+            ],
+            Self::Apng => ["-f", "apng", "-plays", "0"].map(String::from).to_vec(),
+            Self::PngSequence => ["-c:v", "png", "-start_number", "0"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+
+    /// 实际写入的文件名
+    ///
+    /// 图片序列的文件名中没有帧序号格式时，在文件名后追加 `_%05d`。
+    #[must_use]
+    pub fn output_name(self, save_name: &Path) -> PathBuf {
+        if self != Self::PngSequence || save_name.to_string_lossy().contains('%') {
+            return save_name.to_path_buf();
+        }
+        let mut file_name = save_name.file_stem().unwrap_or_default().to_os_string();
+        file_name.push("_%05d.png");
+        save_name.with_file_name(file_name)
+    }
+
+    /// 检查格式参数是否有效
+    pub(super) fn check(self) -> std::result::Result<(), String> {
+        match self {
+            Self::Gif { colors, .. } if !(2..=256).contains(&colors) => {
+                Err(format!("gif colors must be in 2..=256, got {colors}"))
+            }
+            Self::WebP { quality, .. } if quality > 100 => {
+                Err(format!("webp quality must be in 0..=100, got {quality}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 检查格式能否包含音频和章节，只有视频格式支持
+    pub(super) fn check_streams(
+        self,
+        audio: bool,
+        chapters: bool,
+    ) -> std::result::Result<(), String> {
+        if !self.is_video() && (audio || chapters) {
+            return Err(format!("{self:?} does not support audio or chapters"));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    /// 解析 `mp4`、`webm`、`gif[:COLORS]`、`webp[:QUALITY|lossless]`、`apng`、`png-sequence` 格式的输出格式
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, arg) = s.split_once(':').map_or((s, None), |(n, a)| (n, Some(a)));
+        let format = match (name, arg) {
+            ("mp4", None) => Self::Mp4,
+            ("webm", None) => Self::WebM,
+            ("gif", None) => Self::gif(),
+            ("gif", Some(colors)) => Self::Gif {
+                colors: colors.parse().map_err(|e| format!("`{colors}`: {e}"))?,
+                dither: default_dither(),
+            },
+            ("webp", None) => Self::webp(),
+            ("webp", Some("lossless")) => Self::WebP {
+                quality: default_quality(),
+                lossless: true,
+            },
+            ("webp", Some(quality)) => Self::WebP {
+                quality: quality.parse().map_err(|e| format!("`{quality}`: {e}"))?,
+                lossless: false,
+            },
+            ("apng", None) => Self::Apng,
+            ("png-sequence", None) => Self::PngSequence,
+            _ => {
+                return Err(format!(
+                    "expected `mp4`, `webm`, `gif[:COLORS]`, `webp[:QUALITY|lossless]`, `apng` or `png-sequence`, got `{s}`"
+                ))
+            }
+        };
+        format.check()?;
+        Ok(format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_format() {
+        let from_path = |p: &str| OutputFormat::from_path(Path::new(p));
+        assert_eq!(from_path("result.mp4"), Some(OutputFormat::Mp4));
+        assert_eq!(from_path("result.WebM"), Some(OutputFormat::WebM));
+        assert_eq!(from_path("result.gif"), Some(OutputFormat::gif()));
+        assert_eq!(from_path("result.png"), Some(OutputFormat::Apng));
+        assert_eq!(
+            from_path("frames/%05d.png"),
+            Some(OutputFormat::PngSequence)
+        );
+        assert_eq!(from_path("result.mkv"), None);
+
+        assert_eq!(
+            "gif:64".parse(),
+            Ok(OutputFormat::Gif {
+                colors: 64,
+                dither: true
+            })
+        );
+        assert_eq!(
+            "webp:lossless".parse(),
+            Ok(OutputFormat::WebP {
+                quality: 75,
+                lossless: true
+            })
+        );
+        assert!("gif:1".parse::<OutputFormat>().is_err());
+        assert!("webp:101".parse::<OutputFormat>().is_err());
+        assert!(OutputFormat::gif().check_streams(true, false).is_err());
+        assert!(OutputFormat::Apng.check_streams(false, true).is_err());
+        assert!(OutputFormat::WebM.check_streams(true, true).is_ok());
+        assert!(OutputFormat::PngSequence
+            .check_streams(false, false)
+            .is_ok());
+
+        let format: OutputFormat = serde_json::from_str(r#"{"type":"gif","colors":128}"#).unwrap();
+        assert_eq!(
+            format,
+            OutputFormat::Gif {
+                colors: 128,
+                dither: true
+            }
+        );
+        assert_eq!(
+            OutputFormat::PngSequence.output_name(Path::new("frames/result.png")),
+            Path::new("frames/result_%05d.png")
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use super::{BigImg, OutputFormat, Pipeline, Segment, Timeline, PROGRESS_ARGS};
+use crate::error::Result;
+use std::{
+    ffi::OsString,
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// 分段视频在最终视频中的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentPart {
+    /// 封面，画面停在长图开头。
+    Cover,
+    /// 滑动阶段的一段。
+    Scroll,
+    /// 结尾，画面停在长图末尾。
+    Ending,
+}
+
+/// `Segments` 方式生成的一个分段视频
+///
+/// # Parameters
+///
+/// * `name`: 分段视频文件名，相对于工作路径。
+/// * `part`: 分段视频所处的阶段。
+/// * `segment`: 使用的分段长图在时间轴分段中的序号。
+/// * `chunks`: 分段长图包含的图像块在 `chunks` 中的范围。
+/// * `frames`: 分段视频在最终视频中的帧范围。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentVideo {
+    pub name: PathBuf,
+    pub part: SegmentPart,
+    pub segment: usize,
+    pub chunks: Range<usize>,
+    pub frames: Range<u64>,
+}
+
+/// 渲染计划
+///
+/// 由 [`BigImg::plan`] 计算，不读取图片也不调用 `FFmpeg`。
+///
+/// # Parameters
+///
+/// * `format`: 输出格式。
+/// * `pipeline`: 视频生成方式。
+/// * `fps`: 视频帧率。
+/// * `total_frames`: 视频总帧数。
+/// * `duration`: 视频总时长（秒）。
+/// * `segments`: 按顺序合并的分段视频，`Stream` 方式为空。
+/// * `work_dir`: 执行 `FFmpeg` 命令的工作路径。
+/// * `commands`: 按执行顺序排列的 `FFmpeg` 命令，第一个元素为程序路径。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub format: OutputFormat,
+    pub pipeline: Pipeline,
+    pub fps: u32,
+    pub total_frames: u64,
+    pub duration: f64,
+    pub segments: Vec<SegmentVideo>,
+    pub work_dir: PathBuf,
+    pub commands: Vec<Vec<OsString>>,
+}
+
+impl BigImg<'_> {
+    /// 计算渲染计划：分段视频、每段的图像块范围和时长、视频总时长以及完整的 `FFmpeg` 命令。
+    ///
+    /// 只根据参数计算，不读取图片，也不调用 `FFmpeg`。
+    ///
+    /// # Parameters
+    /// - `save_name`: 最终视频文件名，与 [`BigImg::run`] 的参数相同。
+    ///
+    /// # Errors
+    /// - 如果设置了音频或章节而输出格式不是视频，则返回 `Err`。
+    /// - 如果音频文件路径无法解析，则返回 `Err`。
+    ///
+    pub fn plan<P: AsRef<Path>>(&self, save_name: P) -> Result<Plan> {
+        let save_name = save_name.as_ref();
+        let timeline = Timeline::new(self);
+        let format = self.checked_format(save_name)?;
+        let video_name = self.video_name(save_name, format);
+
+        // 与实际执行时相同，逐帧编码以外的命令都通过 stdout 输出编码进度
+        let command = |progress: bool, args: Vec<OsString>| -> Vec<OsString> {
+            let prefix: &[&str] = if progress { &PROGRESS_ARGS } else { &[] };
+            std::iter::once(self.ffmpeg.as_os_str().to_owned())
+                .chain(prefix.iter().map(OsString::from))
+                .chain(args)
+                .collect()
+        };
+        let mut segments = Vec::new();
+        let mut commands = Vec::new();
+        match self.pipeline {
+            Pipeline::Segments => {
+                let divided = timeline.segments(&self.divide(), &self.column_offsets, self.rows);
+                segments = self.segment_videos(&timeline, &divided);
+                for video in &segments {
+                    let args = self.segment_args(&timeline, &divided[video.segment], video);
+                    commands.push(command(true, args));
+                }
+                commands.push(command(true, concat_args(&video_name)));
+            }
+            Pipeline::Stream => commands.push(command(false, self.stream_args(&video_name))),
+        }
+        if video_name != save_name {
+            let args = self.mux_args(&timeline, &video_name, save_name, format, self.chapters)?;
+            commands.push(command(true, args));
+        }
+        Ok(Plan {
+            format,
+            pipeline: self.pipeline,
+            fps: self.video_fps,
+            total_frames: timeline.total_frames(),
+            duration: timeline.duration(),
+            segments,
+            work_dir: self.work_dir.clone(),
+            commands,
+        })
+    }
+
+    /// 列出 `Segments` 方式需要生成的分段视频，按合并顺序排列。
+    ///
+    /// 第一个分段前有封面，最后一个分段后有结尾，没有滑动帧的分段不生成视频。
+    ///
+    /// # Parameters
+    /// - `timeline`: 视频时间轴。
+    /// - `segments`: 时间轴上的分段。
+    ///
+    pub(super) fn segment_videos(
+        &self,
+        timeline: &Timeline,
+        segments: &[Segment],
+    ) -> Vec<SegmentVideo> {
+        let cover = timeline.cover_frames;
+        let scroll_end = cover + timeline.scroll_frames();
+        let mut videos = Vec::with_capacity(segments.len() + 2);
+        for (index, segment) in segments.iter().enumerate() {
+            let video = |name: String, part, frames| SegmentVideo {
+                name: PathBuf::from(name),
+                part,
+                segment: index,
+                chunks: segment.chunks.clone(),
+                frames,
+            };
+            if index == 0 && timeline.cover_frames > 0 {
+                videos.push(video("cover.mp4".into(), SegmentPart::Cover, 0..cover));
+            }
+            if !segment.frames.is_empty() {
+                videos.push(video(
+                    format!("{index:0>2}.mp4"),
+                    SegmentPart::Scroll,
+                    cover + segment.frames.start..cover + segment.frames.end,
+                ));
+            }
+            if index == segments.len() - 1 && timeline.ending_frames > 0 {
+                videos.push(video(
+                    "ending.mp4".into(),
+                    SegmentPart::Ending,
+                    scroll_end..scroll_end + timeline.ending_frames,
+                ));
+            }
+        }
+        videos
+    }
+}
+
+/// 合并分段视频的 `FFmpeg` 参数，文件列表为工作路径中的 `list.txt`
+///
+/// - `-f concat` 指定concat分离器
+/// - `-safe 0` 允许文件名中包含任意字符
+/// - `-i` 输入文件列表
+/// - `-c copy` 使用流拷贝模式（不重新编码）
+/// - `-y` 覆盖输出文件
+pub(super) fn concat_args(save_name: &Path) -> Vec<OsString> {
+    let mut args: Vec<OsString> = ["-f", "concat", "-safe", "0", "-i", "./list.txt"]
+        .map(OsString::from)
+        .into();
+    args.extend(["-c".into(), "copy".into(), "-y".into()]);
+    args.push(super::ffmpeg_path(save_name));
+    args
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "format: {:?}", self.format)?;
+        writeln!(f, "pipeline: {:?}", self.pipeline)?;
+        writeln!(
+            f,
+            "duration: {:.3}s ({} frames at {} fps)",
+            self.duration, self.total_frames, self.fps
+        )?;
+        if !self.segments.is_empty() {
+            writeln!(f, "segments:")?;
+            for video in &self.segments {
+                writeln!(
+                    f,
+                    "  {:<12} chunks {:>4}..{:<4} frames {:>6}..{:<6} {:>8.3}s",
+                    video.name.display(),
+                    video.chunks.start,
+                    video.chunks.end,
+                    video.frames.start,
+                    video.frames.end,
+                    (video.frames.end - video.frames.start) as f64 / f64::from(self.fps)
+                )?;
+            }
+        }
+        writeln!(f, "commands (in {}):", self.work_dir.display())?;
+        for command in &self.commands {
+            let line: Vec<String> = command.iter().map(shell_quote).collect();
+            writeln!(f, "  {}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// 按 POSIX shell 的规则给参数加上单引号，只包含安全字符的参数保持不变
+fn shell_quote(arg: &OsString) -> String {
+    let arg = arg.to_string_lossy();
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=+,%@".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.into_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote(&"./00.mp4".into()), "./00.mp4");
+        assert_eq!(
+            shell_quote(&"color=white:s=960x540:r=10[bg];[bg][0]overlay".into()),
+            "'color=white:s=960x540:r=10[bg];[bg][0]overlay'"
+        );
+        assert_eq!(shell_quote(&"it's".into()), r"'it'\''s'");
+        assert_eq!(shell_quote(&"".into()), "''");
+    }
+}
@@ -0,0 +1,134 @@
+use super::{BigImg, SwipSpeed};
+use crate::{error::Result, prelude::debug_print};
+
+/// 低分辨率快速预览参数
+///
+/// 预览按比例缩小屏幕、图像块和字体，保持与完整视频相同的版式比例和时长，
+/// 只用于快速检查效果。
+///
+/// # Parameters
+///
+/// * `scale`: 尺寸缩放比例，范围为 `(0, 1]`，默认为 `0.25`。
+/// * `fps`: 预览视频的帧率，不超过完整视频的帧率，默认为 `15`。
+/// * `preset`: 编码速度预设，默认为 `ultrafast`，`VP9` 编码器忽略该参数。
+/// * `segments`: 只生成前几个分段，未设置时生成全部分段。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preview {
+    pub scale: f32,
+    pub fps: u32,
+    pub preset: String,
+    pub segments: Option<usize>,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Self {
+            scale: 0.25,
+            fps: 15,
+            preset: String::from("ultrafast"),
+            segments: None,
+        }
+    }
+}
+
+impl Preview {
+    /// 检查预览参数是否有效
+    ///
+    /// # Errors
+    /// - 如果缩放比例不在 `(0, 1]` 范围内、帧率或分段数为零，则返回错误信息。
+    ///
+    pub fn check(&self) -> std::result::Result<(), String> {
+        if !(self.scale > 0.0 && self.scale <= 1.0) {
+            return Err(format!(
+                "preview scale must be in (0, 1], got {}",
+                self.scale
+            ));
+        }
+        if self.fps == 0 {
+            return Err("preview fps must be non-zero".to_string());
+        }
+        if self.segments == Some(0) {
+            return Err("preview segments must be non-zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl BigImg<'_> {
+    /// 将完整视频的参数换算为预览参数。
+    ///
+    /// `width_chunk` 和屏幕尺寸取整到偶数，沿滑动方向的屏幕长度和 `fit_width` 与 `width_chunk` 按相同的比例缩放，
+    /// 因此屏幕上的图像块数量和 `step` 基本不变。`PixelsPerSecond` 滑动速度按沿滑动方向实际的缩放比例换算，
+    /// 预览视频与完整视频的时长相同。
+    ///
+    /// # Parameters
+    /// - `preview`: 预览参数，需要已经通过 [`Preview::check`]。
+    ///
+    /// # Errors
+    /// - 如果重新计算图像块的长度时读取图片尺寸失败，则返回 `Err`。
+    /// - 如果缩放后 `step` 列不足以覆盖一屏，则返回 `Err`。
+    ///
+    pub(super) fn apply_preview(&mut self, preview: &Preview) -> Result<()> {
+        let scale = |v: u32| (v as f32 * preview.scale).round() as u32;
+        let even = |v: u32| (scale(v) / 2 * 2).max(2);
+
+        let width_chunk = even(self.width_chunk);
+        let ratio = width_chunk as f32 / self.width_chunk as f32;
+        let along = |v: u32| ((v as f32 * ratio).round() as u32).max(1);
+        let (main, cross) = self.axis.split(self.screen);
+        let cross = even(cross);
+        self.screen = self.axis.size((along(main) / 2 * 2).max(2), cross);
+        self.width_chunk = width_chunk;
+        self.fit_width = self.fit_width.map(|(min, max)| (along(min), along(max)));
+        let card = cross / self.rows;
+        self.pic_h = scale(self.pic_h).min(card);
+        self.text_up_h = scale(self.text_up_h).min(card - self.pic_h);
+        self.text_down_h = card - self.pic_h - self.text_up_h;
+        self.max_scale *= preview.scale;
+        self.layout_scale = preview.scale;
+        if let SwipSpeed::PixelsPerSecond(pixels) = self.video_swip_speed {
+            self.video_swip_speed = SwipSpeed::PixelsPerSecond(pixels * f64::from(ratio));
+        }
+        self.video_fps = self.video_fps.min(preview.fps);
+        self.encoder.preset = Some(preview.preset.clone());
+        self.column_offsets = self.layout()?;
+        self.check_step()?;
+
+        if let Some(segments) = preview.segments {
+            if let Some(last) = self.divide().get(segments - 1) {
+                self.chunks = &self.chunks[..last.end];
+                self.column_offsets.truncate(self.columns() + 1);
+            }
+        }
+        debug_print(format!(
+            "preview {}x{} at {} fps, {} chunks",
+            self.screen.0,
+            self.screen.1,
+            self.video_fps,
+            self.chunks.len()
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_check() {
+        assert!(Preview::default().check().is_ok());
+        for scale in [0.0, 1.5, f32::NAN] {
+            let preview = Preview {
+                scale,
+                ..Preview::default()
+            };
+            assert!(preview.check().is_err());
+        }
+        let preview = Preview {
+            segments: Some(0),
+            ..Preview::default()
+        };
+        assert!(preview.check().is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use super::BigImg;
+use std::path::PathBuf;
+
+/// 生成视频过程中的进度事件
+///
+/// 通过 [`BigImgBuilder::progress`](super::BigImgBuilder::progress) 设置的回调接收，
+/// 回调可能在绘制图像块的线程中调用。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Progress {
+    /// 图像块绘制完成，`index` 为图像块在 `chunks` 中的序号，`total` 为图像块总数。
+    ChunkDrawn { index: usize, total: usize },
+    /// `FFmpeg` 正在生成 `target`，`percent` 为完成的百分比。
+    Encoding { target: PathBuf, percent: f64 },
+    /// 分段视频 `target` 已生成，`reused` 表示直接复用了工作路径中的视频。
+    /// `index` 从 0 开始计数，`total` 为分段视频总数。
+    SegmentDone {
+        target: PathBuf,
+        index: usize,
+        total: usize,
+        reused: bool,
+    },
+    /// 开始合并分段视频。
+    Concat,
+    /// 开始转换输出格式并混入音频和章节。
+    Mux,
+    /// 开始清理中间文件。
+    Cleanup,
+    /// 视频生成完成。
+    Finished,
+}
+
+impl BigImg<'_> {
+    /// 将进度事件传给回调，没有设置回调时忽略。
+    pub(super) fn emit(&self, event: Progress) {
+        if let Some(progress) = &self.progress {
+            progress(&event);
+        }
+    }
+}
+
+/// 解析 `FFmpeg` 的 `-progress` 输出中的一行，返回完成的百分比。
+///
+/// # Parameters
+/// - `line`: `key=value` 形式的一行输出。
+/// - `duration`: 输出文件的时长（秒）。
+///
+/// # Results
+/// `out_time_us` 行按 `duration` 计算百分比，`progress=end` 行为 `100`，其他行返回 `None`。
+///
+pub(super) fn parse_percent(line: &str, duration: f64) -> Option<f64> {
+    if line.trim() == "progress=end" {
+        return Some(100.0);
+    }
+    let out_time: f64 = line.trim().strip_prefix("out_time_us=")?.parse().ok()?;
+    (duration > 0.0).then(|| (out_time / 1e6 / duration * 100.0).clamp(0.0, 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_percent() {
+        assert_eq!(parse_percent("out_time_us=1500000", 6.0), Some(25.0));
+        assert_eq!(parse_percent("out_time_us=9000000", 6.0), Some(100.0));
+        assert_eq!(parse_percent("out_time_us=N/A", 6.0), None);
+        assert_eq!(parse_percent("out_time_us=1500000", 0.0), None);
+        assert_eq!(parse_percent("frame=15", 6.0), None);
+        assert_eq!(parse_percent("progress=end", 6.0), Some(100.0));
+    }
+}
@@ -0,0 +1,69 @@
+use super::{BigImg, Progress};
+use crate::error::Result;
+use image::DynamicImage;
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+};
+
+impl BigImg<'_> {
+    /// 使用最多 `workers` 个线程并行绘制图像块。
+    ///
+    /// 每个线程依次领取下一个未绘制的图像块，结果按序号顺序返回，与线程数无关。
+    /// 启用缓存时未改变的图像块直接从缓存读取。每个图像块绘制完成后发送 `Progress::ChunkDrawn` 事件。
+    ///
+    /// # Parameters
+    /// - `chunks`: 要绘制的图像块在 `BigImg` 图像块中的序号范围，每个图像块按所在列的长度绘制。
+    ///
+    /// # Errors
+    /// - 如果任一图像块绘制失败，则返回序号最小的错误，并停止领取新的图像块。
+    /// - 如果已经取消，则返回 `Cancelled` 类型错误。
+    ///
+    pub fn render_chunks(&self, chunks: Range<usize>) -> Result<Vec<DynamicImage>> {
+        let draw = |index: usize| {
+            self.check_cancelled()?;
+            let image = self.draw_cached(&self.chunks[index], self.chunk_len(index))?;
+            self.emit(Progress::ChunkDrawn {
+                index,
+                total: self.chunks.len(),
+            });
+            Ok(image)
+        };
+        let workers = self.workers.min(chunks.len());
+        if workers <= 1 {
+            return chunks.map(draw).collect();
+        }
+
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let mut rendered: Vec<(usize, Result<DynamicImage>)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut rendered = Vec::new();
+                        while !failed.load(Ordering::Relaxed) {
+                            let index = chunks.start + next.fetch_add(1, Ordering::Relaxed);
+                            if index >= chunks.end {
+                                break;
+                            }
+                            let image = draw(index);
+                            if image.is_err() {
+                                failed.store(true, Ordering::Relaxed);
+                            }
+                            rendered.push((index, image));
+                        }
+                        rendered
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("render worker panicked"))
+                .collect()
+        });
+
+        rendered.sort_unstable_by_key(|(index, _)| *index);
+        rendered.into_iter().map(|(_, image)| image).collect()
+    }
+}
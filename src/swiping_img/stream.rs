@@ -0,0 +1,117 @@
+use super::{ffmpeg_path, BigImg, Progress, Strip, Timeline};
+use crate::{
+    err_new_ffmpeg,
+    error::{FfmpegError, Result},
+    prelude::debug_print,
+};
+use std::{
+    ffi::OsString,
+    io::{Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+impl BigImg<'_> {
+    /// 逐帧计算画面并通过标准输入以 `rawvideo` 格式传给单个 `FFmpeg` 进程生成视频。
+    ///
+    /// 不生成中间图片和视频文件，也不需要合并步骤。编码进度按写入的帧数计算。
+    ///
+    /// # Parameters
+    /// - `timeline`: 视频时间轴。
+    /// - `save_name`: 最终视频文件名。
+    ///
+    /// # Errors
+    /// - 如果图像处理过程中发生错误，则终止 `FFmpeg` 进程并删除未完成的视频后返回 `Err`。
+    /// - 如果 `FFmpeg` 进程启动失败或返回非零状态码，则返回 `Err`。
+    /// - 如果已经取消，则终止 `FFmpeg` 进程并删除未完成的视频后返回 `Cancelled` 类型错误。
+    ///
+    pub(super) fn run_stream(&self, timeline: &Timeline, save_name: &Path) -> Result<()> {
+        let args = self.stream_args(save_name);
+        let mut child = Command::new(&self.ffmpeg)
+            .current_dir(&self.work_dir)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // FFmpeg 持续向 stderr 输出日志，需要单独读取以免管道写满后阻塞
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stderr = thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        });
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let mut strip = Strip::new(self);
+        let total = timeline.total_frames();
+        // 只有写入标准输入失败说明 FFmpeg 已经退出，绘制画面时的 IO 错误需要单独区分
+        let mut broken_pipe = false;
+        let written = (0..total).try_for_each(|frame| -> Result<()> {
+            self.check_cancelled()?;
+            let image = strip.frame(timeline.offset(frame))?;
+            if let Err(e) = stdin.write_all(image.as_raw()) {
+                broken_pipe = true;
+                return Err(e.into());
+            }
+            // 进度按写入的帧数计算，每增加 1% 发送一次
+            if (frame + 1) * 100 / total != frame * 100 / total {
+                self.emit(Progress::Encoding {
+                    target: save_name.to_path_buf(),
+                    percent: ((frame + 1) * 100 / total) as f64,
+                });
+            }
+            Ok(())
+        });
+        // 取消或绘制失败时先终止 FFmpeg 再关闭标准输入，以免 FFmpeg 把已写入的帧保存为完整的视频；
+        // 写入失败说明 FFmpeg 已经退出，返回 FFmpeg 的错误
+        let killed = written.is_err() && !broken_pipe;
+        if killed {
+            let _ = child.kill();
+        }
+        // 关闭标准输入，通知 FFmpeg 输入结束
+        drop(stdin);
+        let status = child.wait()?;
+        let stderr = stderr.join().unwrap_or_default();
+        if written.is_err() || !status.success() {
+            let _ = std::fs::remove_file(self.work_dir.join(save_name));
+        }
+        if killed {
+            return written;
+        }
+        if !status.success() {
+            return Err(err_new_ffmpeg!(FfmpegError::new(&args, status, &stderr)));
+        }
+        written?;
+
+        println!("{} successed", save_name.display());
+        debug_print(format!("{} frames", timeline.total_frames()));
+        Ok(())
+    }
+
+    /// 逐帧编码的 `FFmpeg` 参数，画面以 `rawvideo` 格式从标准输入读取。
+    pub(super) fn stream_args(&self, save_name: &Path) -> Vec<OsString> {
+        let (w, h) = self.screen;
+        let mut args: Vec<OsString> = ["-f", "rawvideo", "-pix_fmt", "rgba", "-s"]
+            .map(OsString::from)
+            .into();
+        args.extend([
+            format!("{w}x{h}").into(),
+            "-r".into(),
+            self.video_fps.to_string().into(),
+            "-i".into(),
+            "-".into(),
+            "-filter_complex".into(),
+            format!(
+                "color={}:s={w}x{h}:r={}[bg];[bg][0]overlay=shortest=1",
+                self.video_background_color, self.video_fps
+            )
+            .into(),
+        ]);
+        args.extend(self.encoder.args().into_iter().map(OsString::from));
+        args.extend(["-y".into(), ffmpeg_path(save_name)]);
+        args
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Axis, BigImg};
+use crate::error::Result;
+use image::{imageops, DynamicImage, RgbaImage};
+use std::{collections::VecDeque, ops::Range};
+
+/// 按需绘制的滑动长图
+///
+/// 只保留当前屏幕可见范围内的图像块列，随着偏移量变化依次绘制新进入屏幕的列并释放已经滑出屏幕的列，
+/// 避免一次性绘制整张长图。偏移量单调增加或单调减少时每个图像块只绘制一次。
+///
+/// # Parameters
+///
+/// * `si`: 提供图像块数据和绘制参数的 `BigImg`。
+/// * `first`: `tiles` 中第一列的序号。
+/// * `tiles`: 已绘制且仍可能可见的图像块列，每列包含 `rows` 个图像块。
+pub struct Strip<'s, 'a> {
+    si: &'s BigImg<'a>,
+    first: usize,
+    tiles: VecDeque<RgbaImage>,
+}
+
+impl<'s, 'a> Strip<'s, 'a> {
+    #[must_use]
+    pub fn new(si: &'s BigImg<'a>) -> Self {
+        Self {
+            si,
+            first: 0,
+            tiles: VecDeque::new(),
+        }
+    }
+
+    /// 获取偏移量 `offset` 处屏幕可见的画面，长图之外的区域为透明。
+    ///
+    /// 偏移量包含小数部分时，按沿滑动方向相邻的两列（或两行）像素线性插值，实现亚像素定位。
+    ///
+    /// # Parameters
+    /// - `offset`: 屏幕左边缘（纵向滑动时为上边缘）在长图中的位置（像素）。
+    ///
+    /// # Errors
+    /// - 如果绘制图像块时发生错误，则返回 `Err`。
+    ///
+    pub fn frame(&mut self, offset: f64) -> Result<RgbaImage> {
+        let offset = offset.max(0.0);
+        let base = offset.floor();
+        // 小数部分换算为 0..=256 的权重，过小的小数部分直接忽略
+        let weight = ((offset - base) * 256.0).round() as u32;
+        let base = base as u32 + weight / 256;
+        let weight = weight % 256;
+        let axis = self.si.axis;
+        let length = axis.split(self.si.screen).0;
+        if weight == 0 {
+            return self.compose(base, length);
+        }
+
+        let wide = self.compose(base, length + 1)?;
+        let (step_x, step_y) = axis.size(1, 0);
+        let (screen_w, screen_h) = self.si.screen;
+        let frame = RgbaImage::from_fn(screen_w, screen_h, |x, y| {
+            let (a, b) = (
+                wide.get_pixel(x, y).0,
+                wide.get_pixel(x + step_x, y + step_y).0,
+            );
+            image::Rgba(std::array::from_fn(|c| {
+                let v = u32::from(a[c]) * (256 - weight) + u32::from(b[c]) * weight + 128;
+                (v >> 8) as u8
+            }))
+        });
+        Ok(frame)
+    }
+
+    /// 拼接偏移量 `offset` 处沿滑动方向长度为 `length` 的画面。
+    fn compose(&mut self, offset: u32, length: u32) -> Result<RgbaImage> {
+        // 可见的列为包含 `offset` 的列到起始位置在画面末尾之前的最后一列
+        let si = self.si;
+        let offsets = &si.column_offsets;
+        let start = offsets.partition_point(|&o| o <= offset).saturating_sub(1);
+        let end = offsets
+            .partition_point(|&o| o < offset.saturating_add(length))
+            .min(si.columns());
+
+        // 可见范围与已绘制的列不相交时全部重新绘制
+        if start >= self.first + self.tiles.len() || end <= self.first {
+            self.tiles.clear();
+        }
+        // 释放已经滑出屏幕的列
+        while self.first < start && !self.tiles.is_empty() {
+            self.tiles.pop_front();
+            self.first += 1;
+        }
+        while self.first + self.tiles.len() > end {
+            self.tiles.pop_back();
+        }
+        if self.tiles.is_empty() {
+            self.first = start;
+        }
+        // 并行绘制从前方进入屏幕的列
+        if start < self.first {
+            let tiles = self.render(start..self.first)?;
+            for tile in tiles.into_iter().rev() {
+                self.tiles.push_front(tile);
+            }
+            self.first = start;
+        }
+        // 并行绘制从后方进入屏幕的列
+        let next = self.first + self.tiles.len();
+        if next < end {
+            let tiles = self.render(next..end)?;
+            self.tiles.extend(tiles);
+        }
+
+        let axis = self.si.axis;
+        let (w, h) = axis.size(length, axis.split(self.si.screen).1);
+        let mut frame = RgbaImage::new(w, h);
+        for (i, tile) in self
+            .tiles
+            .iter()
+            .enumerate()
+            .take(end.saturating_sub(self.first))
+        {
+            let position = i64::from(offsets[self.first + i]) - i64::from(offset);
+            let (x, y) = match axis {
+                Axis::Horizontal => (position, 0),
+                Axis::Vertical => (0, position),
+            };
+            imageops::replace(&mut frame, tile, x, y);
+        }
+        Ok(frame)
+    }
+
+    /// 并行绘制 `columns` 中的所有图像块，并按列拼接。
+    fn render(&self, columns: Range<usize>) -> Result<Vec<RgbaImage>> {
+        let si = self.si;
+        let tiles = si.render_chunks(si.column_chunks(columns.clone()))?;
+        if si.rows == 1 {
+            return Ok(tiles.into_iter().map(DynamicImage::into_rgba8).collect());
+        }
+        Ok(columns
+            .zip(tiles.chunks(si.rows as usize))
+            .map(|(index, column)| {
+                let (w, h) = si.axis.size(si.column_len(index), si.cross_len());
+                let mut image = RgbaImage::new(w, h);
+                for (row, tile) in (0..).zip(column) {
+                    let (x, y) = si.axis.size(0, row * si.chunk_cross());
+                    imageops::replace(&mut image, &tile.to_rgba8(), x.into(), y.into());
+                }
+                image
+            })
+            .collect())
+    }
+}
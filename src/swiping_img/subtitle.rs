@@ -0,0 +1,188 @@
+use super::{BigImg, Direction, Timeline};
+use std::{fmt::Write, ops::Range};
+
+/// 字幕或章节中的一条
+///
+/// 同一时刻到达屏幕中心的图像块合并为一条。
+///
+/// # Parameters
+///
+/// * `start`: 开始时间（秒）。
+/// * `end`: 结束时间（秒）。
+/// * `chunks`: 对应的图像块在 `chunks` 中的范围。
+/// * `title`: 上方文本，用作章节标题。
+/// * `text`: 上方和下方文本，每行一条，用作字幕内容。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub chunks: Range<usize>,
+    pub title: String,
+    pub text: String,
+}
+
+impl BigImg<'_> {
+    /// 计算每个图像块的中心到达屏幕中心的时间，生成字幕或章节条目。
+    ///
+    /// 开始时已经越过屏幕中心的图像块从视频开始计时，
+    /// 到滑动结束仍未到达屏幕中心的图像块从结尾阶段开始计时。
+    /// 反向滑动时按图像块到达的顺序从后往前排列，往返滑动时只计算去程。
+    ///
+    /// # Parameters
+    /// - `timeline`: 视频时间轴。
+    ///
+    #[must_use]
+    pub fn cues(&self, timeline: &Timeline) -> Vec<Cue> {
+        let half_screen = self.axis.split(self.screen).0 / 2;
+        let frames: Vec<u64> = (0..self.chunks.len())
+            .map(|i| {
+                let column = i / self.rows as usize;
+                let center = self.column_offsets[column] + self.column_len(column) / 2;
+                timeline.frame_at(center.saturating_sub(half_screen))
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..frames.len()).collect();
+        if timeline.direction == Direction::Reverse {
+            order.reverse();
+        }
+        let mut cues: Vec<Cue> = Vec::new();
+        for i in order {
+            let frame = frames[i];
+            let (title, text) = (
+                self.chunks[i].text_up().join(" "),
+                [self.chunks[i].text_up(), self.chunks[i].text_down()]
+                    .concat()
+                    .join("\n"),
+            );
+            match cues.last_mut() {
+                Some(cue) if frames[cue.chunks.start] == frame => {
+                    cue.chunks = cue.chunks.start.min(i)..cue.chunks.end.max(i + 1);
+                    cue.title = format!("{} / {title}", cue.title);
+                    cue.text = format!("{}\n{text}", cue.text);
+                }
+                _ => cues.push(Cue {
+                    start: timeline.time(frame),
+                    end: timeline.duration(),
+                    chunks: i..i + 1,
+                    title,
+                    text,
+                }),
+            }
+        }
+        for i in 1..cues.len() {
+            cues[i - 1].end = cues[i].start;
+        }
+        cues
+    }
+}
+
+/// 生成 `SRT` 字幕
+#[must_use]
+pub fn to_srt(cues: &[Cue]) -> String {
+    cues.iter()
+        .enumerate()
+        .fold(String::new(), |mut out, (i, cue)| {
+            let _ = write!(
+                out,
+                "{}\n{} --> {}\n{}\n\n",
+                i + 1,
+                timestamp(cue.start, ','),
+                timestamp(cue.end, ','),
+                cue.text
+            );
+            out
+        })
+}
+
+/// 生成 `WebVTT` 字幕
+#[must_use]
+pub fn to_vtt(cues: &[Cue]) -> String {
+    cues.iter()
+        .fold(String::from("WEBVTT\n\n"), |mut out, cue| {
+            let _ = write!(
+                out,
+                "{} --> {}\n{}\n\n",
+                timestamp(cue.start, '.'),
+                timestamp(cue.end, '.'),
+                cue.text
+            );
+            out
+        })
+}
+
+/// 生成 `FFmpeg` 的 `FFMETADATA` 章节文件
+#[must_use]
+pub fn to_ffmetadata(cues: &[Cue]) -> String {
+    cues.iter()
+        .fold(String::from(";FFMETADATA1\n"), |mut out, cue| {
+            let _ = write!(
+                out,
+                "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+                (cue.start * 1000.0).round() as u64,
+                (cue.end * 1000.0).round() as u64,
+                escape_metadata(&cue.title)
+            );
+            out
+        })
+}
+
+/// 格式化为 `HH:MM:SS,mmm` 形式的时间戳，`separator` 为秒和毫秒之间的分隔符
+fn timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// 转义 `FFMETADATA` 中的特殊字符
+fn escape_metadata(value: &str) -> String {
+    value.chars().fold(String::new(), |mut out, c| {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let cues = [
+            Cue {
+                start: 0.0,
+                end: 3.5,
+                chunks: 0..2,
+                title: "奥达 / 重岳".to_string(),
+                text: "奥达\n生日\n重岳\n生日".to_string(),
+            },
+            Cue {
+                start: 3.5,
+                end: 3725.25,
+                chunks: 2..3,
+                title: "a=b;c".to_string(),
+                text: "a=b;c".to_string(),
+            },
+        ];
+        assert_eq!(
+            to_srt(&cues),
+            "1\n00:00:00,000 --> 00:00:03,500\n奥达\n生日\n重岳\n生日\n\n\
+             2\n00:00:03,500 --> 01:02:05,250\na=b;c\n\n"
+        );
+        assert!(to_vtt(&cues).starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:03.500\n"));
+        assert_eq!(
+            to_ffmetadata(&cues),
+            ";FFMETADATA1\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=3500\ntitle=奥达 / 重岳\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=3500\nEND=3725250\ntitle=a\\=b\\;c\n"
+        );
+    }
+}
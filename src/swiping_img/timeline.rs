@@ -0,0 +1,494 @@
+use super::{BigImg, Direction, Motion, Scroll};
+use std::ops::Range;
+
+/// 视频的时间轴
+///
+/// 将视频按帧划分为封面、滑动、结尾三个阶段，并给出每一帧对应的滑动偏移量。
+/// 滑动阶段的偏移量由运动曲线按帧采样得到，再按滑动方向翻转或往返。
+///
+/// # Parameters
+///
+/// * `fps`: 视频帧率。
+/// * `cover_frames`: 封面阶段的帧数，偏移量保持为 [`Timeline::start_offset`]。
+/// * `ending_frames`: 结尾阶段的帧数，偏移量保持为 [`Timeline::end_offset`]。
+/// * `distance`: 长图可以滑动的最大偏移量（像素）。
+/// * `speed`: 匀速滑动时的速度（像素每秒）。
+/// * `linear`: 滑动阶段是否为匀速运动。
+/// * `direction`: 滑动方向。
+/// * `offsets`: 滑动阶段每一帧的精确偏移量，可以包含小数部分。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    pub fps: u32,
+    pub cover_frames: u64,
+    pub ending_frames: u64,
+    pub distance: u32,
+    pub speed: f64,
+    pub linear: bool,
+    pub direction: Direction,
+    offsets: Vec<f64>,
+}
+
+/// 分段视频中的一段
+///
+/// # Parameters
+///
+/// * `chunks`: 该段长图包含的图像块在 `chunks` 中的范围。
+/// * `origin`: 该段长图左边缘在整张长图中的位置（像素）。
+/// * `frames`: 该段负责的滑动阶段帧范围，相邻两段首尾相接，互不重叠。
+///   往返滑动时同一组图像块可以对应两段。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub chunks: Range<usize>,
+    pub origin: u32,
+    pub frames: Range<u64>,
+}
+
+impl Timeline {
+    /// 根据 `BigImg` 的参数计算时间轴。
+    ///
+    /// 滑动距离为长图总长度减去一屏的长度（像素）。图像块长度不同时，
+    /// `SecondsPerChunk` 滑动速度和按图像块停顿的间隔按每列的平均长度换算。
+    #[must_use]
+    pub fn new(si: &BigImg) -> Self {
+        let strip_len = si.strip_len();
+        let average = f64::from(strip_len) / si.columns() as f64;
+        let scroll = Scroll {
+            distance: f64::from(strip_len.saturating_sub(si.axis.split(si.screen).0)),
+            speed: si.video_swip_speed.pixels_per_second(average),
+            stride: average,
+        };
+        let fps = u64::from(si.video_fps);
+        Self::sample(
+            si.video_fps,
+            u64::from(si.video_cover_time) * fps,
+            u64::from(si.video_ending_time) * fps,
+            si.motion.as_ref(),
+            &scroll,
+        )
+        .with_direction(si.direction)
+    }
+
+    /// 按帧采样运动曲线，生成时间轴。
+    ///
+    /// # Parameters
+    /// - `fps`: 视频帧率。
+    /// - `cover_frames`: 封面阶段的帧数。
+    /// - `ending_frames`: 结尾阶段的帧数。
+    /// - `motion`: 滑动阶段的运动曲线。
+    /// - `scroll`: 滑动阶段的基本参数。
+    ///
+    #[must_use]
+    pub fn sample(
+        fps: u32,
+        cover_frames: u64,
+        ending_frames: u64,
+        motion: &dyn Motion,
+        scroll: &Scroll,
+    ) -> Self {
+        let distance = scroll.distance as u32;
+        let scroll_frames = if distance == 0 {
+            0
+        } else {
+            (motion.duration(scroll) * f64::from(fps)).round() as u64
+        };
+        let offsets = (0..scroll_frames)
+            .map(|k| {
+                motion
+                    .position(k as f64 / f64::from(fps), scroll)
+                    .min(f64::from(distance))
+            })
+            .collect();
+        Self {
+            fps,
+            cover_frames,
+            ending_frames,
+            distance,
+            speed: scroll.speed,
+            linear: motion.is_linear(),
+            direction: Direction::Forward,
+            offsets,
+        }
+    }
+
+    /// 按滑动方向翻转或往返滑动阶段的偏移量。
+    ///
+    /// `Reverse` 时每一帧的偏移量为 `distance` 减去原偏移量；
+    /// `PingPong` 时在原偏移量之后接上翻转后的偏移量，滑动阶段的帧数加倍。
+    ///
+    /// # Parameters
+    /// - `direction`: 滑动方向。
+    ///
+    #[must_use]
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        let distance = f64::from(self.distance);
+        let reversed = self.offsets.iter().map(|o| distance - o);
+        match direction {
+            Direction::Forward => {}
+            Direction::Reverse => self.offsets = reversed.collect(),
+            Direction::PingPong => {
+                self.offsets = self.offsets.iter().copied().chain(reversed).collect()
+            }
+        }
+        self.direction = direction;
+        self
+    }
+
+    /// 封面阶段的偏移量，`Reverse` 时为 `distance`，其余为 0
+    #[must_use]
+    pub fn start_offset(&self) -> u32 {
+        match self.direction {
+            Direction::Reverse => self.distance,
+            Direction::Forward | Direction::PingPong => 0,
+        }
+    }
+
+    /// 结尾阶段的偏移量，`Forward` 时为 `distance`，其余为 0
+    #[must_use]
+    pub fn end_offset(&self) -> u32 {
+        match self.direction {
+            Direction::Forward => self.distance,
+            Direction::Reverse | Direction::PingPong => 0,
+        }
+    }
+
+    /// 滑动阶段的帧数
+    #[must_use]
+    pub fn scroll_frames(&self) -> u64 {
+        self.offsets.len() as u64
+    }
+
+    /// 视频总帧数
+    #[must_use]
+    pub fn total_frames(&self) -> u64 {
+        self.cover_frames + self.scroll_frames() + self.ending_frames
+    }
+
+    /// 视频总时长（秒）
+    #[must_use]
+    pub fn duration(&self) -> f64 {
+        self.time(self.total_frames())
+    }
+
+    /// 获取第 `frame` 帧的精确滑动偏移量（像素），可以包含小数部分。
+    ///
+    /// 封面阶段为 [`Timeline::start_offset`]，滑动阶段为运动曲线的采样值，
+    /// 结尾阶段及之后为 [`Timeline::end_offset`]。
+    #[must_use]
+    pub fn offset(&self, frame: u64) -> f64 {
+        let Some(k) = frame.checked_sub(self.cover_frames) else {
+            return f64::from(self.start_offset());
+        };
+        usize::try_from(k)
+            .ok()
+            .and_then(|k| self.offsets.get(k))
+            .copied()
+            .unwrap_or(f64::from(self.end_offset()))
+    }
+
+    /// 获取第 `frame` 帧取整到像素的滑动偏移量。
+    #[must_use]
+    pub fn pixel_offset(&self, frame: u64) -> u32 {
+        to_pixel(self.offset(frame))
+    }
+
+    /// 沿滑动方向，取整后的偏移量首次达到 `offset` 的帧（相对于视频开始）。
+    ///
+    /// 封面阶段已经达到时返回 0；滑动阶段（往返滑动时为去程）仍未达到时返回其后的第一帧。
+    #[must_use]
+    pub fn frame_at(&self, offset: u32) -> u64 {
+        let (pass, forward) = match self.direction {
+            Direction::Forward => (&self.offsets[..], true),
+            Direction::Reverse => (&self.offsets[..], false),
+            Direction::PingPong => (&self.offsets[..self.offsets.len() / 2], true),
+        };
+        let start = self.start_offset();
+        if (forward && start >= offset) || (!forward && start <= offset) {
+            return 0;
+        }
+        let k = if forward {
+            pass.partition_point(|&o| to_pixel(o) < offset)
+        } else {
+            pass.partition_point(|&o| to_pixel(o) > offset)
+        };
+        self.cover_frames + k as u64
+    }
+
+    /// 第 `frame` 帧开始的时间（秒）
+    #[must_use]
+    pub fn time(&self, frame: u64) -> f64 {
+        frame as f64 / f64::from(self.fps)
+    }
+
+    /// 第 `t` 秒显示的帧，超过视频时长时为最后一帧。
+    ///
+    /// 与 [`Timeline::time`] 互逆，允许 `t` 有微小的浮点误差。
+    #[must_use]
+    pub fn frame_of(&self, t: f64) -> u64 {
+        let frame = (t.max(0.0) * f64::from(self.fps) + PIXEL_EPSILON).floor() as u64;
+        frame.min(self.total_frames().saturating_sub(1))
+    }
+
+    /// 将滑动阶段按 `divide` 得到的图像块范围划分为首尾相接的分段，按时间顺序排列。
+    ///
+    /// 每一帧属于 `origin` 不超过其取整偏移量的最后一个图像块范围，连续属于同一范围的帧组成一个分段，
+    /// 保证分段视频合并后偏移量连续，没有重复帧。没有滑动帧时只有一个包含封面偏移量的空分段。
+    ///
+    /// # Parameters
+    /// - `ranges`: 每一分段包含的图像块范围，按 `start` 递增排列，从一列的开头开始。
+    /// - `column_offsets`: 每列图像块在长图中沿滑动方向的起始位置（像素）。
+    /// - `rows`: 每列图像块的数量。
+    ///
+    #[must_use]
+    pub fn segments(
+        &self,
+        ranges: &[Range<usize>],
+        column_offsets: &[u32],
+        rows: u32,
+    ) -> Vec<Segment> {
+        let origins: Vec<u32> = ranges
+            .iter()
+            .map(|r| column_offsets[r.start / rows as usize])
+            .collect();
+        let range_of = |offset: u32| origins.partition_point(|&o| o <= offset).saturating_sub(1);
+        let segment = |i: usize, frames| Segment {
+            chunks: ranges[i].clone(),
+            origin: origins[i],
+            frames,
+        };
+
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut current = None;
+        for (k, &offset) in (0..).zip(&self.offsets) {
+            let i = range_of(to_pixel(offset));
+            match segments.last_mut() {
+                Some(last) if current == Some(i) => last.frames.end = k + 1,
+                _ => segments.push(segment(i, k..k + 1)),
+            }
+            current = Some(i);
+        }
+        if segments.is_empty() {
+            segments.push(segment(range_of(self.start_offset()), 0..0));
+        }
+        segments
+    }
+
+    /// 生成 `FFmpeg` `overlay` 滤镜的 `x` 表达式，使分段视频第 `n` 帧与时间轴上的偏移量一致。
+    ///
+    /// 只适用于匀速运动（`linear` 为 `true`）的时间轴，`overlay` 只能按整像素定位。
+    /// 往返滑动时跨越折返点的分段用 `if` 表达式分别计算去程和回程的偏移量。
+    #[must_use]
+    pub fn overlay_x(&self, segment: &Segment) -> String {
+        let forward = |k: &str| {
+            format!(
+                "floor({:?}*({k})/{}+{PIXEL_EPSILON:?})",
+                self.speed, self.fps
+            )
+        };
+        let backward = |k: &str| {
+            format!(
+                "floor({}-{:?}*({k})/{}+{PIXEL_EPSILON:?})",
+                self.distance, self.speed, self.fps
+            )
+        };
+        let start = segment.frames.start;
+        let offset = match self.direction {
+            Direction::Forward => forward(&format!("{start}+n")),
+            Direction::Reverse => backward(&format!("{start}+n")),
+            Direction::PingPong => {
+                let turn = self.scroll_frames() / 2;
+                if segment.frames.end <= turn {
+                    forward(&format!("{start}+n"))
+                } else if start >= turn {
+                    backward(&format!("{}+n", start - turn))
+                } else {
+                    format!(
+                        "if(lt(n,{}),{},{})",
+                        turn - start,
+                        forward(&format!("{start}+n")),
+                        backward(&format!("{start}+n-{turn}"))
+                    )
+                }
+            }
+        };
+        format!("-({offset}-{})", segment.origin)
+    }
+}
+
+/// 偏移量和帧序号取整时允许的浮点误差，避免整数值因浮点误差被向下取整
+const PIXEL_EPSILON: f64 = 1e-9;
+
+/// 将精确偏移量取整到像素
+fn to_pixel(offset: f64) -> u32 {
+    (offset + PIXEL_EPSILON).floor() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swiping_img::MotionProfile;
+
+    #[test]
+    fn test_offset() {
+        let scroll = Scroll {
+            distance: 1440.0,
+            speed: 160.0,
+            stride: 480.0,
+        };
+        let timeline = Timeline::sample(10, 20, 30, &MotionProfile::Linear, &scroll);
+        assert_eq!(timeline.scroll_frames(), 90);
+        assert_eq!(timeline.total_frames(), 140);
+        assert_eq!(timeline.offset(0), 0.0);
+        assert_eq!(timeline.offset(20), 0.0);
+        assert_eq!(timeline.offset(21), 16.0);
+        assert_eq!(timeline.offset(65), 720.0);
+        assert_eq!(timeline.offset(109), 1424.0);
+        assert_eq!(timeline.offset(110), 1440.0);
+        assert_eq!(timeline.offset(1000), 1440.0);
+        assert_eq!(timeline.frame_at(0), 0);
+        assert_eq!(timeline.frame_at(16), 21);
+        assert_eq!(timeline.frame_at(17), 22);
+        assert_eq!(timeline.frame_at(1440), 110);
+        assert_eq!(timeline.frame_at(5000), 110);
+        assert_eq!(timeline.frame_of(0.0), 0);
+        assert_eq!(timeline.frame_of(2.3), 23);
+        assert_eq!(timeline.frame_of(13.99), 139);
+        assert_eq!(timeline.frame_of(100.0), 139);
+
+        let step_hold = MotionProfile::StepHold { hold: 1.0 };
+        let timeline = Timeline::sample(10, 20, 30, &step_hold, &scroll);
+        assert_eq!(timeline.scroll_frames(), 110);
+        assert_eq!(timeline.pixel_offset(50), 480);
+        assert_eq!(timeline.pixel_offset(60), 480);
+        assert_eq!(timeline.pixel_offset(61), 496);
+
+        // 480 / 7 像素每秒，30 帧每秒，每帧滑动的像素数不是整数
+        let scroll = Scroll {
+            distance: 1440.0,
+            speed: 480.0 / 7.0,
+            stride: 480.0,
+        };
+        let timeline = Timeline::sample(30, 0, 0, &MotionProfile::Linear, &scroll);
+        assert_eq!(timeline.scroll_frames(), 630);
+        assert_eq!(timeline.pixel_offset(7), 16);
+        assert!((timeline.offset(7) - 16.0).abs() < 1e-9);
+        assert_eq!(timeline.pixel_offset(8), 18);
+        assert!((timeline.offset(8) - 128.0 / 7.0).abs() < 1e-9);
+        // 不会因为整数除法而漂移
+        assert_eq!(timeline.pixel_offset(629), 1437);
+        assert_eq!(timeline.offset(630), 1440.0);
+    }
+
+    #[test]
+    fn test_directions() {
+        // 12 个图像块，step = 6，overlap = 4，每个图像块 2 秒，10 帧每秒
+        let (width_chunk, screen_w, len, step, overlap) = (240, 960, 12, 6, 4);
+        let scroll = Scroll {
+            distance: f64::from((len - overlap) as u32 * width_chunk),
+            speed: f64::from(width_chunk) / 2.0,
+            stride: f64::from(width_chunk),
+        };
+        let ranges: Vec<_> = (0..len - overlap)
+            .step_by(step - overlap)
+            .map(|i| i..(i + step).min(len))
+            .collect();
+        let offsets: Vec<u32> = (0..=len as u32).map(|c| c * width_chunk).collect();
+        let forward = Timeline::sample(10, 10, 10, &MotionProfile::Linear, &scroll);
+
+        let reverse = forward.clone().with_direction(Direction::Reverse);
+        assert_eq!(reverse.scroll_frames(), forward.scroll_frames());
+        assert_eq!(reverse.offset(0), 1920.0);
+        assert_eq!(reverse.offset(11), 1908.0);
+        assert_eq!(reverse.offset(reverse.total_frames() - 1), 0.0);
+        assert_eq!(reverse.frame_at(1920), 0);
+        assert_eq!(reverse.frame_at(1908), 11);
+        assert_eq!(reverse.frame_at(0), 10 + 160);
+
+        let ping_pong = forward.clone().with_direction(Direction::PingPong);
+        assert_eq!(ping_pong.scroll_frames(), 2 * forward.scroll_frames());
+        assert_eq!(ping_pong.offset(10 + 160), 1920.0);
+        assert_eq!(ping_pong.offset(10 + 320), 0.0);
+        assert_eq!(ping_pong.frame_at(1920), 10 + 160);
+
+        for timeline in [forward, reverse, ping_pong] {
+            let segments = timeline.segments(&ranges, &offsets, 1);
+            // 所有分段首尾相接，每一帧都在分段长图的范围内
+            assert_eq!(segments[0].frames.start, 0);
+            assert!(segments
+                .windows(2)
+                .all(|w| w[0].frames.end == w[1].frames.start && w[0].chunks != w[1].chunks));
+            assert_eq!(
+                segments.last().unwrap().frames.end,
+                timeline.scroll_frames()
+            );
+            for segment in &segments {
+                let strip_w = segment.chunks.len() as u32 * width_chunk;
+                for n in segment.frames.clone() {
+                    let offset = timeline.pixel_offset(timeline.cover_frames + n);
+                    assert!(offset >= segment.origin, "{segment:?} frame {n}");
+                    assert!(offset - segment.origin + screen_w <= strip_w);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_segments_rows() {
+        // 17 个图像块排成 2 行 9 列，屏幕显示 4 列，每个分段 6 列
+        let scroll = Scroll {
+            distance: 1200.0,
+            speed: 120.0,
+            stride: 240.0,
+        };
+        let timeline = Timeline::sample(10, 0, 0, &MotionProfile::Linear, &scroll);
+        let offsets: Vec<u32> = (0..=9).map(|c| c * 240).collect();
+        let segments = timeline.segments(&[0..12, 4..16, 8..17], &offsets, 2);
+        let origins: Vec<u32> = segments.iter().map(|s| s.origin).collect();
+        assert_eq!(origins, [0, 480, 960]);
+        assert_eq!(segments[1].frames, 40..80);
+        assert_eq!(segments[2].frames, 80..100);
+    }
+
+    #[test]
+    fn test_segments_continuous() {
+        // 25 个图像块，step = 10，overlap = 4，每个图像块 7 秒，30 帧每秒
+        let (width_chunk, screen_w, len, step, overlap) = (480, 1920, 25, 10, 4);
+        let steps = len - overlap;
+        let scroll = Scroll {
+            distance: f64::from(steps as u32 * width_chunk),
+            speed: f64::from(width_chunk) / 7.0,
+            stride: f64::from(width_chunk),
+        };
+        let timeline = Timeline::sample(30, 90, 90, &MotionProfile::Linear, &scroll);
+        let ranges: Vec<_> = (0..len - overlap)
+            .step_by(step - overlap)
+            .map(|i| i..(i + step).min(len))
+            .collect();
+        let columns: Vec<u32> = (0..=len as u32).map(|c| c * width_chunk).collect();
+        let segments = timeline.segments(&ranges, &columns, 1);
+
+        let mut offsets = Vec::new();
+        for segment in &segments {
+            let strip_w = segment.chunks.len() as u32 * width_chunk;
+            for n in segment.frames.clone() {
+                let local = timeline.pixel_offset(timeline.cover_frames + n) - segment.origin;
+                // 分段长图足够覆盖整个屏幕
+                assert!(local + screen_w <= strip_w, "{segment:?} frame {n}");
+                offsets.push(segment.origin + local);
+            }
+        }
+        // 所有分段首尾相接，覆盖整个滑动阶段
+        assert_eq!(segments[0].frames.start, 0);
+        assert!(segments
+            .windows(2)
+            .all(|w| w[0].frames.end == w[1].frames.start));
+        assert_eq!(offsets.len() as u64, timeline.scroll_frames());
+
+        // 偏移量从封面开始，逐帧平滑增加，最后与结尾衔接
+        let max_step = timeline.distance.div_ceil(timeline.scroll_frames() as u32);
+        assert_eq!(offsets[0], timeline.pixel_offset(timeline.cover_frames - 1));
+        assert!(offsets
+            .windows(2)
+            .all(|w| w[1] >= w[0] && w[1] - w[0] <= max_step));
+        assert!(timeline.distance - offsets[offsets.len() - 1] <= max_step);
+    }
+}